@@ -0,0 +1,99 @@
+//! # Checksums
+//! Real DSMR meters follow every telegram with a `!XXXX` footer: a CRC16 over the bytes of the
+//! telegram, from the start of its first line up to and including the `!`.
+//!
+//! Here the footer is the line right after the `END` line of an outermost telegram, so the
+//! checksum also covers the telegrams nested inside it. Footers are optional, except under
+//! [`ChecksumPolicy::Reject`].
+use crate::error::{parse_error, MainError};
+
+/// What the parser does with a checksum footer
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ChecksumPolicy {
+    /// A telegram with a wrong or missing checksum is a parse error
+    Reject,
+    /// A telegram with a wrong checksum is kept, its `checksum` tells that it is wrong
+    Warn,
+    /// Footers are skipped without verifying them
+    #[default]
+    Ignore,
+}
+
+impl TryFrom<&str> for ChecksumPolicy {
+    type Error = MainError;
+
+    fn try_from(policy: &str) -> Result<Self, Self::Error> {
+        match policy {
+            "reject" => Ok(ChecksumPolicy::Reject),
+            "warn" => Ok(ChecksumPolicy::Warn),
+            "ignore" => Ok(ChecksumPolicy::Ignore),
+            _ => Err(MainError::UsageError(format!(
+                "Unknown checksum policy {policy}"
+            ))),
+        }
+    }
+}
+
+/// The checksum footer of a telegram, next to the checksum of the bytes that were received
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checksum {
+    pub expected: u16,
+    pub computed: u16,
+}
+
+impl Checksum {
+    pub fn is_valid(&self) -> bool {
+        self.expected == self.computed
+    }
+
+    /// Fails with [`MainError::ChecksumMismatch`] if the checksums differ
+    pub fn check(&self) -> Result<(), MainError> {
+        if !self.is_valid() {
+            return Err(MainError::ChecksumMismatch {
+                expected: self.expected,
+                computed: self.computed,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// CRC16 as used by DSMR (CRC-16/ARC: polynomial 0x8005, reflected, initial value 0)
+pub fn crc16(bytes: &[u8]) -> u16 {
+    crc16_update(0, bytes)
+}
+
+/// Continues the CRC16 `crc` of earlier bytes with `bytes`
+pub fn crc16_update(mut crc: u16, bytes: &[u8]) -> u16 {
+    for byte in bytes {
+        crc ^= *byte as u16;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xA001
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    crc
+}
+
+pub fn is_footer(line: &str) -> bool {
+    line.starts_with('!')
+}
+
+/// Parses a `!XXXX` footer into the checksum it carries
+pub fn parse_footer(line: &str) -> Result<u16, MainError> {
+    let hex = line
+        .strip_prefix('!')
+        .ok_or_else(|| parse_error("Checksum footer must start with !"))?;
+    if hex.len() != 4 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(parse_error("Checksum must be 4 hexadecimal digits"));
+    }
+    u16::from_str_radix(hex, 16).map_err(|_| parse_error("Checksum must be 4 hexadecimal digits"))
+}
+
+pub fn serialize_footer(crc: u16) -> String {
+    format!("!{crc:04X}")
+}
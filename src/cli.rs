@@ -0,0 +1,434 @@
+//! # Command line interface
+//! Parses the arguments of the `dsmr` binary and runs the requested command, see [`USAGE`].
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use crate::checksum::ChecksumPolicy;
+use crate::error::MainError;
+use crate::export::{
+    currents_to_csv, energy_to_csv, gas_to_csv, heat_to_csv, tariffs_to_csv, to_json,
+    voltages_to_csv, water_to_csv,
+};
+use crate::generator::{generate_text, GeneratorConfig};
+use crate::helpers::{
+    current_phases, energy_series, gas_series, group_by_meter, heat_series, read_from_stdin,
+    tariff_series, voltage_phases, water_series,
+};
+use crate::obis::Dialect;
+use crate::parser::{parse_tree_and_header, ParseOptions, ParserConfig};
+use crate::runner::{report, sort_by_date};
+use crate::serializer::{serialize, serialize_date};
+use crate::telegram::{Telegram, YearPivot};
+
+pub const USAGE: &str = "\
+usage: dsmr [command] [options] [inputs...]
+
+Inputs are file paths or glob patterns, `-` (or no inputs at all) reads from stdin.
+
+commands:
+    parse [--lenient] [inputs...]          print the telegrams in the input
+    report [--out <dir>] [inputs...]       generate the graphs and eventlog (the default)
+    validate [inputs...]                   report every problem in the input
+    export [--format <format>] [--out <file>] [inputs...]
+                                           convert the input, formats: dsmr, json, csv
+    generate [--seed <n>] [--count <n>] [--interval <seconds>] [--v10] [--no-gas] [--nested]
+                                           write synthetic telegrams to stdout
+    help                                   print this message
+
+options:
+    --checksum <reject|warn|ignore>        what parse, report, validate and export do with
+                                           checksum footers (default: ignore)
+    --dialect <simplified|obis>            how the ids of the lines are written
+                                           (default: taken from the header)
+    --year-pivot <n>                       two digit years below n are in the 2000s, the
+                                           others in the 1900s (default: 100)
+
+exit codes:
+    0 success, 1 invalid arguments, 2 invalid input, 3 IO error, 4 plot error";
+
+#[derive(Debug, PartialEq)]
+pub enum ExportFormat {
+    /// The text format the parser reads
+    Dsmr,
+    /// See [`crate::export`] for the schema
+    Json,
+    /// One file per series of [`crate::helpers`], written to the `--out` directory
+    Csv,
+}
+
+#[derive(Debug)]
+pub enum Command {
+    Parse {
+        inputs: Vec<String>,
+        options: ParseOptions,
+    },
+    Report {
+        inputs: Vec<String>,
+        /// The graph library only takes its output folder from the `DSMR_OUTPUT_PATH`
+        /// environment variable. `execute` leaves the environment alone, the binary sets it.
+        out: Option<PathBuf>,
+        options: ParseOptions,
+    },
+    Validate {
+        inputs: Vec<String>,
+        options: ParseOptions,
+    },
+    Export {
+        inputs: Vec<String>,
+        format: ExportFormat,
+        out: Option<PathBuf>,
+        options: ParseOptions,
+    },
+    Generate(GeneratorConfig),
+    Help,
+}
+
+/// Exit code of the binary for the result of a command, printing the error if it failed
+pub fn exit_code(result: Result<(), MainError>) -> ExitCode {
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {e}");
+            if let MainError::UsageError(_) = e {
+                eprintln!("\n{USAGE}");
+            }
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+pub fn parse_args(args: &[String]) -> Result<Command, MainError> {
+    let (command, mut args) = match args.split_first() {
+        Some((command, rest)) if !command.starts_with('-') || command == "-" => {
+            (command.as_str(), rest.iter())
+        }
+        // Without a command we generate the report, just like the assignment always did
+        _ => ("report", args.iter()),
+    };
+
+    let mut inputs: Vec<String> = Vec::new();
+    let mut options = ParseOptions::default();
+    let mut out: Option<PathBuf> = None;
+    let mut format = ExportFormat::Dsmr;
+    let mut generator = GeneratorConfig::default();
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .cloned()
+                .ok_or_else(|| MainError::UsageError(format!("{name} expects a value")))
+        };
+        let number = |name: &str, value: String| {
+            value
+                .parse::<u64>()
+                .map_err(|_| MainError::UsageError(format!("{name} expects a number")))
+        };
+
+        match (command, arg.as_str()) {
+            ("parse", "--lenient") => options.lenient = true,
+            ("parse" | "report" | "validate" | "export", "--checksum") => {
+                options.checksum = ChecksumPolicy::try_from(value("--checksum")?.as_str())?
+            }
+            ("parse" | "report" | "validate" | "export", "--dialect") => {
+                options.dialect = Some(Dialect::try_from(value("--dialect")?.as_str())?)
+            }
+            ("parse" | "report" | "validate" | "export", "--year-pivot") => {
+                let pivot = number(arg, value(arg)?)?;
+                if pivot > 100 {
+                    return Err(MainError::UsageError(
+                        "--year-pivot expects a number up to 100".to_string(),
+                    ));
+                }
+                options.year_pivot = YearPivot(pivot as u8);
+            }
+            ("report" | "export", "--out") => out = Some(PathBuf::from(value("--out")?)),
+            ("export", "--format") => {
+                format = match value("--format")?.as_str() {
+                    "dsmr" => ExportFormat::Dsmr,
+                    "json" => ExportFormat::Json,
+                    "csv" => ExportFormat::Csv,
+                    other => {
+                        return Err(MainError::UsageError(format!(
+                            "Unknown export format {other}"
+                        )))
+                    }
+                }
+            }
+            ("generate", "--seed") => generator.seed = number(arg, value(arg)?)?,
+            ("generate", "--count") => generator.count = number(arg, value(arg)?)? as usize,
+            ("generate", "--interval") => generator.interval = number(arg, value(arg)?)? as i64,
+            ("generate", "--v10") => {
+                generator.version = (1, 0);
+                generator.is_gas = false;
+            }
+            ("generate", "--no-gas") => generator.is_gas = false,
+            ("generate", "--nested") => generator.is_recursive = true,
+            (_, flag) if flag.starts_with('-') && flag != "-" => {
+                return Err(MainError::UsageError(format!(
+                    "Unknown option {flag} for {command}"
+                )))
+            }
+            ("generate" | "help", input) => {
+                return Err(MainError::UsageError(format!(
+                    "{command} does not take inputs, got {input}"
+                )))
+            }
+            (_, input) => inputs.push(input.to_string()),
+        }
+    }
+
+    match command {
+        "parse" => Ok(Command::Parse { inputs, options }),
+        "report" => Ok(Command::Report {
+            inputs,
+            out,
+            options,
+        }),
+        "validate" => Ok(Command::Validate { inputs, options }),
+        "export" => Ok(Command::Export {
+            inputs,
+            format,
+            out,
+            options,
+        }),
+        "generate" => Ok(Command::Generate(generator)),
+        "help" => Ok(Command::Help),
+        other => Err(MainError::UsageError(format!("Unknown command {other}"))),
+    }
+}
+
+pub fn execute(command: Command) -> Result<(), MainError> {
+    match command {
+        Command::Parse { inputs, options } => {
+            for (name, input) in read_inputs(&inputs)? {
+                let (_, telegrams, _) = parse_reporting(&name, &input, &options)?;
+                println!("{name}: {} telegrams", count(&telegrams));
+                print_telegrams(&telegrams)?;
+            }
+            Ok(())
+        }
+        Command::Report {
+            inputs,
+            out: _,
+            options,
+        } => {
+            let mut telegrams = Vec::new();
+            for (name, input) in read_inputs(&inputs)? {
+                let (_, roots, _) = parse_reporting(&name, &input, &options)?;
+                telegrams.extend(roots.into_iter().flat_map(Telegram::flatten));
+            }
+            report(&sort_by_date(telegrams))
+        }
+        Command::Validate { inputs, options } => {
+            let options = ParseOptions {
+                lenient: true,
+                ..options
+            };
+            let mut problems = 0;
+            for (name, input) in read_inputs(&inputs)? {
+                let (_, telegrams, found) = parse_reporting(&name, &input, &options)?;
+                println!("{name}: {} telegrams, {found} problems", count(&telegrams));
+                problems += found;
+            }
+            if problems > 0 {
+                return Err(crate::error::parse_error(&format!(
+                    "Found {problems} problems"
+                )));
+            }
+            Ok(())
+        }
+        Command::Export {
+            inputs,
+            format,
+            out,
+            options,
+        } => {
+            let mut configs: Vec<ParserConfig> = Vec::new();
+            let mut telegrams: Vec<Telegram> = Vec::new();
+            for (name, input) in read_inputs(&inputs)? {
+                let (config, roots, _) = parse_reporting(&name, &input, &options)?;
+                configs.push(config);
+                telegrams.extend(roots);
+            }
+
+            let output = match format {
+                ExportFormat::Dsmr => serialize(&merge_configs(&configs)?, &telegrams)?,
+                ExportFormat::Json => to_json(&merge_configs(&configs)?, &telegrams)?,
+                ExportFormat::Csv => {
+                    let dir = out.ok_or_else(|| {
+                        MainError::UsageError("CSV export needs --out <dir>".to_string())
+                    })?;
+                    let telegrams = telegrams.into_iter().flat_map(Telegram::flatten).collect();
+                    return write_meters_csv(&dir, sort_by_date(telegrams));
+                }
+            };
+            match out {
+                Some(out) => fs::write(out, output)?,
+                None => print!("{output}"),
+            }
+            Ok(())
+        }
+        Command::Generate(config) => {
+            print!("{}", generate_text(&config)?);
+            Ok(())
+        }
+        Command::Help => {
+            println!("{USAGE}");
+            Ok(())
+        }
+    }
+}
+
+/// Writes the CSV files of every meter. With more than one meter, each gets its own
+/// subdirectory of `dir`, named after its equipment identifier.
+fn write_meters_csv(dir: &Path, telegrams: Vec<Telegram>) -> Result<(), MainError> {
+    let meters = group_by_meter(telegrams);
+    if meters.len() <= 1 {
+        let telegrams = meters.first().map(|m| &m.telegrams[..]).unwrap_or_default();
+        return write_csv(dir, telegrams);
+    }
+    for meter in &meters {
+        let name = match &meter.equipment_id {
+            Some(id) => id.replace(|c: char| !c.is_ascii_alphanumeric() && c != '-', "_"),
+            None => "unknown".to_string(),
+        };
+        write_csv(&dir.join(name), &meter.telegrams)?;
+    }
+    Ok(())
+}
+
+/// Writes `voltages.csv`, `currents.csv`, `gas.csv`, `energy.csv`, `tariffs.csv`, `water.csv`
+/// and `heat.csv` to `dir`
+fn write_csv(dir: &Path, telegrams: &[Telegram]) -> Result<(), MainError> {
+    fs::create_dir_all(dir)?;
+    fs::write(
+        dir.join("voltages.csv"),
+        voltages_to_csv(&voltage_phases(telegrams))?,
+    )?;
+    fs::write(
+        dir.join("currents.csv"),
+        currents_to_csv(&current_phases(telegrams))?,
+    )?;
+    fs::write(dir.join("gas.csv"), gas_to_csv(&gas_series(telegrams)?)?)?;
+    fs::write(
+        dir.join("energy.csv"),
+        energy_to_csv(&energy_series(telegrams)?)?,
+    )?;
+    fs::write(
+        dir.join("tariffs.csv"),
+        tariffs_to_csv(&tariff_series(telegrams)?)?,
+    )?;
+    fs::write(
+        dir.join("water.csv"),
+        water_to_csv(&water_series(telegrams)?)?,
+    )?;
+    fs::write(dir.join("heat.csv"), heat_to_csv(&heat_series(telegrams)?)?)?;
+    Ok(())
+}
+
+/// Reads every input, expanding glob patterns. Returns the name and contents of each input.
+pub fn read_inputs(inputs: &[String]) -> Result<Vec<(String, String)>, MainError> {
+    let stdin = ["-".to_string()];
+    let inputs = if inputs.is_empty() {
+        &stdin[..]
+    } else {
+        inputs
+    };
+
+    let mut result = Vec::new();
+    for pattern in inputs {
+        for name in expand(pattern)? {
+            let input = if name == "-" {
+                read_from_stdin()?
+            } else {
+                fs::read_to_string(&name)?
+            };
+            result.push((name, input));
+        }
+    }
+    Ok(result)
+}
+
+fn expand(pattern: &str) -> Result<Vec<String>, MainError> {
+    if pattern == "-" || !pattern.contains(['*', '?', '[']) {
+        return Ok(vec![pattern.to_string()]);
+    }
+
+    let paths = glob::glob(pattern)
+        .map_err(|e| MainError::UsageError(format!("Invalid pattern {pattern}: {e}")))?;
+    let mut names = Vec::new();
+    for path in paths {
+        let path = path.map_err(|e| MainError::IoError(e.into()))?;
+        names.push(path.display().to_string());
+    }
+    if names.is_empty() {
+        return Err(MainError::UsageError(format!("No files match {pattern}")));
+    }
+    Ok(names)
+}
+
+/// Parses (trees of) telegrams, printing the location of every problem and, with
+/// [`ChecksumPolicy::Warn`], a warning for every wrong checksum.
+/// Also returns the header and the number of problems that were skipped when parsing leniently.
+fn parse_reporting(
+    name: &str,
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(ParserConfig, Vec<Telegram>, usize), MainError> {
+    let (config, telegrams, diagnostics) =
+        parse_tree_and_header(input, options).inspect_err(|e| {
+            if let MainError::Diagnostic(d) = e {
+                eprintln!("{name}:\n{}", d.render());
+            }
+        })?;
+    for d in &diagnostics {
+        eprintln!("{name}:\n{}", d.render());
+    }
+    if options.checksum == ChecksumPolicy::Warn {
+        for checksum in telegrams.iter().filter_map(|t| t.checksum) {
+            if let Err(e) = checksum.check() {
+                eprintln!("{name}: warning: {e}");
+            }
+        }
+    }
+    Ok((config, telegrams, diagnostics.len()))
+}
+
+/// Header that can hold the telegrams of all inputs
+fn merge_configs(configs: &[ParserConfig]) -> Result<ParserConfig, MainError> {
+    let is_gas = configs.iter().any(|c| c.is_gas);
+    let is_recursive = configs.iter().any(|c| c.is_recursive);
+    let is_utility = configs.iter().any(|c| c.is_utility);
+    // The OBIS dialect still reads simplified ids, so it can hold every input
+    let is_obis = configs.iter().any(|c| c.dialect == Dialect::Obis);
+    let version = configs.iter().map(|c| c.version).max().unwrap_or((1, 0));
+    let version = if is_gas || is_recursive || is_utility || is_obis {
+        version.max((1, 2))
+    } else {
+        version
+    };
+    let mut config = ParserConfig::new(version, is_gas, is_recursive)?;
+    config.is_utility = is_utility;
+    if is_obis {
+        config.dialect = Dialect::Obis;
+    }
+    Ok(config)
+}
+
+fn count(telegrams: &[Telegram]) -> usize {
+    telegrams.iter().map(Telegram::count).sum()
+}
+
+fn print_telegrams(telegrams: &[Telegram]) -> Result<(), MainError> {
+    for (depth, telegram) in telegrams.iter().flat_map(|t| t.iter().with_depth()) {
+        let date = serialize_date(&telegram.base.date)?;
+        let information_type = telegram.base.information_type.code();
+        println!(
+            "{}{date} {information_type} ({} events)",
+            "  ".repeat(depth + 1),
+            telegram.base.eventlog.len()
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+//! # Decimal
+//! Readings are written as fixed-point numbers, e.g. `0011454892.*kWh` or `12345.123*m3`.
+//! [`Decimal`] keeps them exactly as written, so the difference between two cumulative counters
+//! is exact. Convert to `f64` with [`Decimal::to_f64`] only to plot.
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::{parse_error, MainError};
+
+/// At most this many digits fit in the mantissa
+const MAX_DIGITS: usize = 18;
+/// At most this many decimals, so that any two decimals can be compared exactly
+pub const MAX_SCALE: u32 = 18;
+
+/// `mantissa * 10^-scale`, e.g. `12345.120` is `Decimal::new(12345120, 3)`.
+/// The scale is the number of decimals the value was written with, it does not affect
+/// comparisons: `1.5 == 1.50`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Decimal {
+    mantissa: i64,
+    scale: u32,
+}
+
+impl Decimal {
+    pub const ZERO: Decimal = Decimal::new(0, 0);
+
+    /// Panics if `scale` is larger than [`MAX_SCALE`]
+    pub const fn new(mantissa: i64, scale: u32) -> Self {
+        assert!(scale <= MAX_SCALE, "decimal scale out of range");
+        Self { mantissa, scale }
+    }
+
+    pub fn mantissa(self) -> i64 {
+        self.mantissa
+    }
+
+    /// The number of decimals
+    pub fn scale(self) -> u32 {
+        self.scale
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.mantissa < 0
+    }
+
+    pub fn checked_abs(self) -> Option<Self> {
+        Some(Self::new(self.mantissa.checked_abs()?, self.scale))
+    }
+
+    pub fn checked_neg(self) -> Option<Self> {
+        Some(Self::new(self.mantissa.checked_neg()?, self.scale))
+    }
+
+    /// The same value with `scale` decimals, `None` if that would lose digits or overflow
+    pub fn rescale(self, scale: u32) -> Option<Self> {
+        if scale > MAX_SCALE {
+            return None;
+        }
+        let mantissa = if scale >= self.scale {
+            self.mantissa
+                .checked_mul(10i64.checked_pow(scale - self.scale)?)?
+        } else {
+            let factor = 10i64.checked_pow(self.scale - scale)?;
+            if self.mantissa % factor != 0 {
+                return None;
+            }
+            self.mantissa / factor
+        };
+        Some(Self::new(mantissa, scale))
+    }
+
+    /// The same value without trailing zero decimals, e.g. `1.50` becomes `1.5`
+    pub fn normalize(self) -> Self {
+        let mut normalized = self;
+        while normalized.scale > 0 && normalized.mantissa % 10 == 0 {
+            normalized = Self::new(normalized.mantissa / 10, normalized.scale - 1);
+        }
+        normalized
+    }
+
+    /// The value as a whole number, `None` if it has a fraction
+    pub fn to_integer(self) -> Option<i64> {
+        self.rescale(0).map(Decimal::mantissa)
+    }
+
+    /// The nearest `f64`, for plotting
+    pub fn to_f64(self) -> f64 {
+        // Parsing the decimal text rounds correctly, dividing by a power of ten does not
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// The shortest decimal that converts back to exactly `value`
+    pub fn from_f64(value: f64) -> Result<Self, MainError> {
+        if !value.is_finite() {
+            return Err(parse_error(&format!("{value} is not a decimal")));
+        }
+        value.to_string().parse()
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let sum = self
+            .rescale(scale)?
+            .mantissa
+            .checked_add(other.rescale(scale)?.mantissa)?;
+        Some(Self::new(sum, scale))
+    }
+
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        self.checked_add(other.checked_neg()?)
+    }
+
+    /// Both mantissas at the larger scale. Unlike [`Decimal::rescale`] this cannot overflow,
+    /// as 18 digits times `10^MAX_SCALE` fits in an `i128`.
+    fn aligned(self, other: Self) -> (i128, i128) {
+        let scale = self.scale.max(other.scale);
+        let widen = |d: Decimal| d.mantissa as i128 * 10i128.pow(scale - d.scale);
+        (widen(self), widen(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = self.aligned(*other);
+        a.cmp(&b)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = MainError;
+
+    /// An optional sign, digits and an optional point with more digits, e.g. `-005.01`,
+    /// `0011454892.` or `.5`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || parse_error(&format!("Invalid decimal {s}"));
+        let (negative, unsigned) = match s.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, s.strip_prefix('+').unwrap_or(s)),
+        };
+        let (whole, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        let digits = format!("{whole}{fraction}");
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return Err(invalid());
+        }
+        let significant = digits.trim_start_matches('0');
+        if significant.len() > MAX_DIGITS || fraction.len() > MAX_SCALE as usize {
+            return Err(invalid());
+        }
+
+        let mantissa: i64 = significant.parse().unwrap_or(0);
+        Ok(Self::new(
+            if negative { -mantissa } else { mantissa },
+            fraction.len() as u32,
+        ))
+    }
+}
+
+impl fmt::Display for Decimal {
+    /// Written with all of its decimals, e.g. `12345.120`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let scale = self.scale as usize;
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (whole, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{whole}.{fraction}")
+    }
+}
+
+/// Serialized as text, e.g. `"12345.120"`, so no precision is lost
+#[cfg(feature = "serde")]
+impl serde::Serialize for Decimal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Decimal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(serde::de::Error::custom)
+    }
+}
@@ -0,0 +1,118 @@
+//! # Parse diagnostics
+//! Locates a parse failure inside the source text, so it can be reported with the offending line.
+use std::fmt::Display;
+use std::ops::Range;
+
+use crate::error::MainError;
+use crate::obis::{self, is_obis_line};
+use crate::parser::{determine_content_type, parse_id};
+
+#[derive(Debug)]
+pub struct ParseDiagnostic {
+    /// 1-based line number within the input
+    pub line: usize,
+    /// Byte range within `source` that caused the failure
+    pub span: Range<usize>,
+    /// The id part of the line (everything before `#`, or the OBIS code), if there is one
+    pub id: Option<String>,
+    /// The value part of the line (everything between the outer parentheses), if there is one
+    pub value: Option<String>,
+    /// The source line itself
+    pub source: String,
+    /// What actually went wrong
+    pub reason: MainError,
+}
+
+impl ParseDiagnostic {
+    pub fn new(line: usize, source: &str, reason: MainError) -> Self {
+        let (id, value, id_is_valid) = if is_obis_line(source) {
+            obis_parts(source)
+        } else {
+            simplified_parts(source)
+        };
+
+        // Blame the value, unless the id already is not understood
+        let span = match (id, value) {
+            (Some(_), Some(value)) if id_is_valid && !value.is_empty() => {
+                let start = value.as_ptr() as usize - source.as_ptr() as usize;
+                start..start + value.len()
+            }
+            (Some(id), _) if !id_is_valid && !id.is_empty() => 0..id.len(),
+            _ => 0..source.len(),
+        };
+
+        Self {
+            line,
+            span,
+            id: id.map(str::to_string),
+            value: value.map(str::to_string),
+            source: source.to_string(),
+            reason,
+        }
+    }
+
+    /// 1-based column of the start of the span
+    pub fn column(&self) -> usize {
+        self.span.start + 1
+    }
+
+    /// Prints the source line with the offending part underlined, e.g.
+    /// ```text
+    /// error: Invalid voltage value
+    ///  --> line 3, column 8
+    ///   |
+    /// 3 | 7.1.1#(02x1.7*V)
+    ///   |        ^^^^^^^^
+    /// ```
+    pub fn render(&self) -> String {
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let underline = format!(
+            "{}{}",
+            " ".repeat(self.source[..self.span.start].chars().count()),
+            "^".repeat(self.source[self.span.clone()].chars().count().max(1))
+        );
+
+        format!(
+            "error: {}\n{gutter}--> line {}, column {}\n{gutter} |\n{line_number} | {}\n{gutter} | {underline}",
+            self.reason,
+            self.line,
+            self.column(),
+            self.source,
+        )
+    }
+}
+
+/// The id and value of a simplified line, e.g. `7.1.1#(0230.1*V)`, and whether the id is known
+fn simplified_parts(source: &str) -> (Option<&str>, Option<&str>, bool) {
+    let (id, value) = match source.split_once('#') {
+        Some((id, value)) => (Some(id), Some(value)),
+        None => (None, None),
+    };
+    let value = value.map(|v| v.strip_prefix('(').unwrap_or(v));
+    let value = value.map(|v| v.strip_suffix(')').unwrap_or(v));
+
+    let id_is_valid = id.is_some_and(|id| {
+        parse_id(id)
+            .and_then(|id| determine_content_type(&id))
+            .is_ok()
+    });
+    (id, value, id_is_valid)
+}
+
+/// The OBIS code and value of an OBIS line, e.g. `1-0:32.7.0(230.1*V)`, and whether the code
+/// is known. Of a line with more than one value, the last one is the reading.
+fn obis_parts(source: &str) -> (Option<&str>, Option<&str>, bool) {
+    let Some((code, values)) = source.split_once('(') else {
+        return (None, None, false);
+    };
+    let values = values.strip_suffix(')').unwrap_or(values);
+    let value = values.rsplit(")(").next().unwrap_or(values);
+    (Some(code), Some(value), obis::is_known(code))
+}
+
+impl Display for ParseDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.reason)
+    }
+}
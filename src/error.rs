@@ -8,11 +8,46 @@ use std::{error::Error, fmt::Display, io};
 
 use tudelft_dsmr_output_generator::PlotError;
 
+use crate::diagnostic::ParseDiagnostic;
+use crate::telegram::TelegramContentType;
+
 #[derive(Debug)]
 pub enum MainError {
     IoError(io::Error),
     PlotError(PlotError),
     ParseError(io::Error),
+    /// A date that does not exist on the calendar (or cannot be turned into a timestamp)
+    InvalidDate(String),
+    /// A content type that requires a unit was constructed without one
+    MissingUnit(TelegramContentType),
+    /// An eventlog severity other than `H` or `L`
+    UnknownSeverity(String),
+    /// An eventlog message that is not a valid hex-encoded ASCII string
+    BadHexMessage(String),
+    /// An eventlog entry of which the severity, message or date is missing
+    MissingEventlogEntry {
+        id: u32,
+        field: &'static str,
+    },
+    /// An eventlog entry with more than one severity, message or date
+    DuplicateEventlogEntry {
+        id: u32,
+        field: &'static str,
+    },
+    /// A telegram without a usable date field
+    InvalidTimestamp,
+    /// The result of a calculation with decimals does not fit, e.g. the difference of two
+    /// counters with very different numbers of decimals
+    DecimalOverflow(String),
+    /// Any of the above, located at a specific line of the input
+    Diagnostic(Box<ParseDiagnostic>),
+    /// Invalid command line arguments
+    UsageError(String),
+    /// The checksum footer of a telegram does not match its contents
+    ChecksumMismatch {
+        expected: u16,
+        computed: u16,
+    },
 }
 
 // Define how to print out the error when it occurs based on the type of error it is
@@ -22,6 +57,58 @@ impl Display for MainError {
             MainError::IoError(e) => write!(f, "IO Error Occured: {e}"),
             MainError::PlotError(e) => write!(f, "Plot Error Occured: {e}"),
             MainError::ParseError(e) => write!(f, "Parse Error Occured: {e}"),
+            MainError::InvalidDate(date) => write!(f, "Invalid date: {date}"),
+            MainError::MissingUnit(content_type) => {
+                write!(f, "Missing unit for {content_type:?}")
+            }
+            MainError::UnknownSeverity(severity) => {
+                write!(f, "Unknown eventlog severity: {severity}")
+            }
+            MainError::BadHexMessage(message) => {
+                write!(f, "Invalid hex encoded eventlog message: {message}")
+            }
+            MainError::MissingEventlogEntry { id, field } => {
+                write!(f, "Eventlog {id} misses its {field}")
+            }
+            MainError::DuplicateEventlogEntry { id, field } => {
+                write!(f, "Eventlog {id} has more than one {field}")
+            }
+            MainError::InvalidTimestamp => write!(f, "Telegram has no valid timestamp"),
+            MainError::DecimalOverflow(calculation) => {
+                write!(f, "Decimal out of range: {calculation}")
+            }
+            MainError::Diagnostic(d) => write!(f, "{d}"),
+            MainError::UsageError(e) => write!(f, "Usage Error: {e}"),
+            MainError::ChecksumMismatch { expected, computed } => write!(
+                f,
+                "Checksum mismatch: footer says {expected:04X}, telegram has {computed:04X}"
+            ),
+        }
+    }
+}
+
+impl MainError {
+    /// Process exit code of the command line interface when it fails with this error:
+    /// * `1` - invalid command line arguments
+    /// * `2` - the input could not be parsed
+    /// * `3` - reading or writing files failed
+    /// * `4` - generating the graphs failed
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            MainError::UsageError(_) => 1,
+            MainError::ParseError(_)
+            | MainError::InvalidDate(_)
+            | MainError::MissingUnit(_)
+            | MainError::UnknownSeverity(_)
+            | MainError::BadHexMessage(_)
+            | MainError::MissingEventlogEntry { .. }
+            | MainError::DuplicateEventlogEntry { .. }
+            | MainError::InvalidTimestamp
+            | MainError::DecimalOverflow(_)
+            | MainError::ChecksumMismatch { .. }
+            | MainError::Diagnostic(_) => 2,
+            MainError::IoError(_) => 3,
+            MainError::PlotError(_) => 4,
         }
     }
 }
@@ -55,17 +142,7 @@ pub fn parse_error(msg: &str) -> MainError {
     MainError::from(msg)
 }
 
-#[macro_export]
-macro_rules! bail {
-    // Same effect as (makes compilation easier):
-    // fn bail(message: &str) {
-    //     eprintln!("{message}");
-    //     std::process::exit(42);
-    // }
-    ($message:literal) => {{
-        eprintln!("{}", $message);
-        std::process::exit(42);
-    }};
+/// Attaches the location of `line` to `reason`
+pub fn diagnostic(line_number: usize, line: &str, reason: MainError) -> MainError {
+    MainError::Diagnostic(Box::new(ParseDiagnostic::new(line_number, line, reason)))
 }
-
-pub use bail; // Note that we do not do `use crate::error::bail;` -> Macro's can only be exported on top of the top-level crate. I do not grasp *why*...
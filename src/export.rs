@@ -0,0 +1,363 @@
+//! # Export
+//! Converts telegrams into formats other tooling can read.
+//!
+//! ## JSON schema
+//! [`to_json`] writes a single object. Fields are never removed or renamed without
+//! increasing `schema`, new fields may be added at any time.
+//!
+//! Cumulative counters are written as decimal strings with the decimals of the telegram, e.g.
+//! `"0012345.120"` becomes `"12345.120"`, so no precision is lost. Instantaneous readings
+//! (voltage, current and power) are numbers.
+//!
+//! ```text
+//! {
+//!   "schema": 2,
+//!   "version": "1.2",            // telegram format version from the header
+//!   "gas": true,                 // header has the gas extension
+//!   "recursive": false,          // header has the recursive extension
+//!   "utility": false,            // header has the utilities (water and heat) extension
+//!   "telegrams": [Telegram]      // in input order
+//! }
+//!
+//! Telegram = {
+//!   "timestamp": 1688563601,     // unix timestamp (seconds, UTC)
+//!   "date": "2023-07-05T15:26:41",  // local time as written in the telegram
+//!   "dst": true,                 // summer time
+//!   "equipment_id": null | "decoded text",  // the meter that sent the telegram
+//!   "information_type": "E",     // "E" electricity, "G" gas, "W" water, "H" heat
+//!   "electricity": null | {
+//!     "voltage": [f64],          // V, per phase, starting with phase 1
+//!     "current": [f64],          // A, per phase, starting with phase 1
+//!     "power": [f64],            // kW, per phase, starting with phase 1
+//!     "total_consumed": "decimal",  // kWh, cumulative
+//!     "total_produced": "decimal",  // kWh, cumulative
+//!     "tariffs": null | {        // only for dual-tariff meters
+//!       "consumed": ["decimal"; 2],  // kWh, cumulative, for tariff 1 (low) and 2 (normal)
+//!       "produced": ["decimal"; 2],  // kWh, cumulative, for tariff 1 (low) and 2 (normal)
+//!       "active": 1 | 2          // the active tariff
+//!     },
+//!     "quality": null | {        // only for meters that report power quality
+//!       "power_failures": u32,   // number of power failures in any phase
+//!       "long_power_failures": u32,
+//!       "failure_log": [{        // the most recent long power failures
+//!         "index": 1,
+//!         "timestamp": 1688563601,  // when the power came back
+//!         "date": "2023-07-05T15:26:41",
+//!         "dst": true,
+//!         "duration": u64        // seconds
+//!       }],
+//!       "voltage_sags": [u32],   // per phase, empty if not reported
+//!       "voltage_swells": [u32]  // per phase, empty if not reported
+//!     }
+//!   },
+//!   "gas": null | {
+//!     "total_delivered": "decimal"  // m3, cumulative
+//!   },
+//!   "water": null | {
+//!     "total_delivered": "decimal"  // m3, cumulative
+//!   },
+//!   "heat": null | {
+//!     "total_delivered": "decimal"  // GJ, cumulative
+//!   },
+//!   "events": [{
+//!     "index": 1,
+//!     "severity": "H" | "L",
+//!     "message": "decoded text",
+//!     "timestamp": 1688563601,
+//!     "date": "2023-07-05T15:26:41",
+//!     "dst": true
+//!   }],
+//!   "extra": [{                  // vendor specific lines, see crate::registry
+//!     "type": "name",            // name of the custom content type
+//!     "value": "text" | "decimal" | "2023-07-05T15:26:41",
+//!     "unit": null | "unit"
+//!   }],
+//!   "children": [Telegram]       // nested telegrams, only with the recursive extension
+//! }
+//! ```
+//!
+//! ## CSV
+//! The `*_to_csv` functions write the series of [`crate::helpers`] with one row per timestamp.
+//! The first two columns are the unix timestamp and the same moment in UTC, the others have
+//! their unit in their name. Differences of cumulative counters keep their decimals, like in JSON.
+use std::fmt::{Display, Write};
+
+use chrono::DateTime;
+use serde_json::{json, Value as Json};
+
+use crate::error::{parse_error, MainError};
+use crate::helpers::{HeatData, PhaseData, TariffEnergyData};
+use crate::parser::ParserConfig;
+use crate::telegram::*;
+use tudelft_dsmr_output_generator::energy_over_time::EnergyData;
+use tudelft_dsmr_output_generator::gas_over_time::GasData;
+use tudelft_dsmr_output_generator::water_over_time::WaterData;
+use tudelft_dsmr_output_generator::UnixTimeStamp;
+
+/// Version of the JSON schema, see the [module documentation](self)
+pub const JSON_SCHEMA_VERSION: u32 = 2;
+
+/// Writes the header and (trees of) telegrams as pretty printed JSON
+pub fn to_json(config: &ParserConfig, telegrams: &[Telegram]) -> Result<String, MainError> {
+    let value = json_value(config, telegrams)?;
+    serde_json::to_string_pretty(&value).map_err(|e| MainError::IoError(e.into()))
+}
+
+/// Same as [`to_json`], but returns the JSON document instead of its text
+pub fn json_value(config: &ParserConfig, telegrams: &[Telegram]) -> Result<Json, MainError> {
+    let telegrams = telegrams
+        .iter()
+        .map(telegram_to_json)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(json!({
+        "schema": JSON_SCHEMA_VERSION,
+        "version": format!("{}.{}", config.version.0, config.version.1),
+        "gas": config.is_gas,
+        "recursive": config.is_recursive,
+        "utility": config.is_utility,
+        "telegrams": telegrams,
+    }))
+}
+
+fn telegram_to_json(telegram: &Telegram) -> Result<Json, MainError> {
+    let base = &telegram.base;
+    let date = &base.date;
+
+    let (mut electricity, mut gas, mut water, mut heat) =
+        (Json::Null, Json::Null, Json::Null, Json::Null);
+    match &telegram.data {
+        TelegramData::Electricity {
+            voltages,
+            currents,
+            powers,
+            total_consumed,
+            total_produced,
+            tariffs,
+            quality,
+        } => {
+            let tariffs = match tariffs {
+                Some(tariffs) => json!({
+                    "consumed": counters_of(&tariffs.consumed),
+                    "produced": counters_of(&tariffs.produced),
+                    "active": tariffs.active.number(),
+                }),
+                None => Json::Null,
+            };
+            electricity = json!({
+                "voltage": values_of(voltages),
+                "current": values_of(currents),
+                "power": values_of(powers),
+                "total_consumed": total_consumed.0.to_string(),
+                "total_produced": total_produced.0.to_string(),
+                "tariffs": tariffs,
+                "quality": match quality {
+                    Some(quality) => quality_to_json(quality),
+                    None => Json::Null,
+                },
+            });
+        }
+        TelegramData::Gas {
+            total_gas_delivered,
+        } => gas = json!({ "total_delivered": total_gas_delivered.0.to_string() }),
+        TelegramData::Water {
+            total_water_delivered,
+        } => water = json!({ "total_delivered": total_water_delivered.0.to_string() }),
+        TelegramData::Heat {
+            total_heat_delivered,
+        } => heat = json!({ "total_delivered": total_heat_delivered.0.to_string() }),
+    }
+
+    let mut events = Vec::new();
+    for entry in &base.eventlog {
+        events.push(json!({
+            "index": entry.index,
+            "severity": entry.severity.code(),
+            "message": entry.message,
+            "timestamp": entry.date.timestamp,
+            "date": iso_date(&entry.date),
+            "dst": entry.date.dst,
+        }));
+    }
+
+    let extra = telegram
+        .extra
+        .iter()
+        .map(extra_to_json)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let children = telegram
+        .children
+        .iter()
+        .map(telegram_to_json)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(json!({
+        "timestamp": date.timestamp,
+        "date": iso_date(date),
+        "dst": date.dst,
+        "equipment_id": base.equipment_id,
+        "information_type": base.information_type.code(),
+        "electricity": electricity,
+        "gas": gas,
+        "water": water,
+        "heat": heat,
+        "events": events,
+        "extra": extra,
+        "children": children,
+    }))
+}
+
+fn quality_to_json(quality: &PowerQuality) -> Json {
+    let failure_log: Vec<Json> = quality
+        .failure_log
+        .iter()
+        .map(|failure| {
+            json!({
+                "index": failure.index,
+                "timestamp": failure.end.timestamp,
+                "date": iso_date(&failure.end),
+                "dst": failure.end.dst,
+                "duration": failure.duration,
+            })
+        })
+        .collect();
+
+    json!({
+        "power_failures": quality.power_failures,
+        "long_power_failures": quality.long_power_failures,
+        "failure_log": failure_log,
+        "voltage_sags": quality.voltage_sags,
+        "voltage_swells": quality.voltage_swells,
+    })
+}
+
+fn extra_to_json(content: &TelegramContent) -> Result<Json, MainError> {
+    let name = match &content.telegram_content_type {
+        TelegramContentType::Custom(name) => name,
+        other => return Err(parse_error(&format!("{other:?} is not a custom content"))),
+    };
+    let value = match &content.value {
+        Some(Value::String(value)) => json!(value),
+        Some(Value::Number(value)) => json!(value.to_string()),
+        Some(Value::Date(date)) => json!(iso_date(date)),
+        None => Json::Null,
+    };
+    let unit = match &content.unit {
+        Some(TelegramContentUnit::Custom(unit)) => json!(unit),
+        Some(unit) => json!(format!("{unit:?}")),
+        None => Json::Null,
+    };
+
+    Ok(json!({ "type": name, "value": value, "unit": unit }))
+}
+
+/// See [`crate::helpers::voltage_phases`]. Has a column for every phase.
+pub fn voltages_to_csv(voltages: &[PhaseData]) -> Result<String, MainError> {
+    phases_to_csv(voltages, "v")
+}
+
+/// See [`crate::helpers::current_phases`]. Has a column for every phase.
+pub fn currents_to_csv(currents: &[PhaseData]) -> Result<String, MainError> {
+    phases_to_csv(currents, "a")
+}
+
+fn phases_to_csv(series: &[PhaseData], unit: &str) -> Result<String, MainError> {
+    let phases = series.iter().map(|p| p.phases.len()).max().unwrap_or(0);
+    let columns: Vec<String> = (1..=phases)
+        .map(|phase| format!("phase_{phase}_{unit}"))
+        .collect();
+    let columns: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let rows = series.iter().map(|p| (p.timestamp, p.phases.clone()));
+    csv(&columns, rows)
+}
+
+/// See [`crate::helpers::gas_series`]
+pub fn gas_to_csv(gas: &[GasData]) -> Result<String, MainError> {
+    let rows = gas.iter().map(|g| (g.timestamp, vec![g.gas_delta]));
+    csv(&["gas_delta_m3"], rows)
+}
+
+/// See [`crate::helpers::water_series`]
+pub fn water_to_csv(water: &[WaterData]) -> Result<String, MainError> {
+    let rows = water
+        .iter()
+        .map(|w| (w.timestamp, vec![w.water_delta as f64]));
+    csv(&["water_delta_l"], rows)
+}
+
+/// See [`crate::helpers::heat_series`]
+pub fn heat_to_csv(heat: &[HeatData]) -> Result<String, MainError> {
+    let rows = heat
+        .iter()
+        .map(|h| (h.timestamp, vec![h.heat_delta]));
+    csv(&["heat_delta_gj"], rows)
+}
+
+/// See [`crate::helpers::energy_series`]
+pub fn energy_to_csv(energy: &[EnergyData]) -> Result<String, MainError> {
+    let rows = energy
+        .iter()
+        .map(|e| (e.timestamp, vec![e.consumed, e.produced]));
+    csv(&["consumed_kwh", "produced_kwh"], rows)
+}
+
+/// See [`crate::helpers::tariff_series`]
+pub fn tariffs_to_csv(tariffs: &[TariffEnergyData]) -> Result<String, MainError> {
+    let rows = tariffs.iter().map(|t| {
+        let values = vec![
+            t.consumed[0].to_string(),
+            t.consumed[1].to_string(),
+            t.produced[0].to_string(),
+            t.produced[1].to_string(),
+            t.active_tariff.number().to_string(),
+        ];
+        (t.timestamp, values)
+    });
+    csv(
+        &[
+            "consumed_t1_kwh",
+            "consumed_t2_kwh",
+            "produced_t1_kwh",
+            "produced_t2_kwh",
+            "active_tariff",
+        ],
+        rows,
+    )
+}
+
+/// Rows with fewer values than there are columns get empty cells
+fn csv<T: Display>(
+    columns: &[&str],
+    rows: impl Iterator<Item = (UnixTimeStamp, Vec<T>)>,
+) -> Result<String, MainError> {
+    let mut result = format!("timestamp,utc,{}\n", columns.join(","));
+    for (timestamp, values) in rows {
+        let utc = DateTime::from_timestamp(timestamp, 0).ok_or(MainError::InvalidTimestamp)?;
+        // Writing to a String cannot fail
+        let _ = write!(result, "{timestamp},{}", utc.format("%Y-%m-%dT%H:%M:%SZ"));
+        for value in &values {
+            let _ = write!(result, ",{value}");
+        }
+        for _ in values.len()..columns.len() {
+            result.push(',');
+        }
+        result.push('\n');
+    }
+    Ok(result)
+}
+
+fn iso_date(date: &Date) -> String {
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+        date.year, date.month, date.day, date.hour, date.minute, date.seconds
+    )
+}
+
+fn values_of<Q: Quantity>(quantities: &[Q]) -> Vec<f64> {
+    quantities.iter().map(|q| q.value()).collect()
+}
+
+fn counters_of(counters: &[Energy]) -> Vec<String> {
+    counters.iter().map(|counter| counter.0.to_string()).collect()
+}
@@ -0,0 +1,261 @@
+//! # Telegram generator
+//! Produces realistic, reproducible sequences of telegrams for fixtures and load testing.
+use chrono::TimeDelta;
+
+use crate::decimal::Decimal;
+use crate::error::MainError;
+use crate::parser::ParserConfig;
+use crate::serializer::serialize;
+use crate::telegram::*;
+
+const EVENT_MESSAGES: [(Severity, &str); 4] = [
+    (Severity::High, "Power Failure"),
+    (Severity::Low, "Voltage too low"),
+    (Severity::Low, "Voltage too high"),
+    (Severity::High, "Tamper detected"),
+];
+
+/// How many eventlog entries a meter remembers (and repeats in every telegram)
+const EVENT_MEMORY: usize = 3;
+
+#[derive(Clone, Debug)]
+pub struct GeneratorConfig {
+    /// Same seed, same output
+    pub seed: u64,
+    /// Number of (outermost) telegrams
+    pub count: usize,
+    /// Protocol version, either (1, 0) or (1, 2)
+    pub version: (u32, u32),
+    /// Also generate gas readings (v1.2 only)
+    pub is_gas: bool,
+    /// Nest the gas telegram inside the electricity telegram (v1.2 only)
+    pub is_recursive: bool,
+    /// Unix timestamp of the first telegram
+    pub start: i64,
+    /// Seconds between two telegrams
+    pub interval: i64,
+    /// Chance of a new eventlog entry in each telegram
+    pub event_probability: f64,
+}
+
+impl Default for GeneratorConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            count: 100,
+            version: (1, 2),
+            is_gas: true,
+            is_recursive: false,
+            // 2023-01-01 00:00:00 (W)
+            start: 1672527600,
+            interval: 60,
+            event_probability: 0.05,
+        }
+    }
+}
+
+/// Small deterministic PRNG (SplitMix64), so generated fixtures never change
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1)
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn range(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+}
+
+struct Event {
+    id: u32,
+    severity: Severity,
+    message: &'static str,
+    date: Date,
+}
+
+struct Generator {
+    config: GeneratorConfig,
+    rng: Rng,
+    timestamp: i64,
+    /// Cumulative counters are kept in Wh and liters, so they stay exact
+    consumed_wh: u64,
+    produced_wh: u64,
+    gas_liters: u64,
+    events: Vec<Event>,
+    next_event_id: u32,
+}
+
+/// Generates a header and a sequence of telegrams according to `config`
+/// Invalid options are a [`MainError::UsageError`], as they come from the command line.
+pub fn generate(config: &GeneratorConfig) -> Result<(ParserConfig, Vec<Telegram>), MainError> {
+    let usage_error = |message: &str| Err(MainError::UsageError(message.to_string()));
+    if config.version == (1, 0) && (config.is_gas || config.is_recursive) {
+        return usage_error("Version 1.0 has no gas or nested telegrams");
+    }
+    if config.is_recursive && !config.is_gas {
+        return usage_error("Nesting requires gas telegrams to nest");
+    }
+    if config.interval <= 0 {
+        return usage_error("Interval must be positive");
+    }
+    let parser_config = ParserConfig::new(config.version, config.is_gas, config.is_recursive)?;
+
+    let mut rng = Rng(config.seed);
+    let consumed_wh = 1_000_000 + rng.next_u64() % 1_000_000;
+    let mut generator = Generator {
+        config: config.clone(),
+        rng,
+        timestamp: config.start,
+        consumed_wh,
+        produced_wh: 0,
+        gas_liters: 1_000_000,
+        events: Vec::new(),
+        next_event_id: 1,
+    };
+
+    let mut telegrams = Vec::new();
+    for _ in 0..config.count {
+        telegrams.extend(generator.next_telegrams()?);
+        generator.timestamp += config.interval;
+    }
+    Ok((parser_config, telegrams))
+}
+
+/// Same as [`generate`], but already serialized into the text format
+pub fn generate_text(config: &GeneratorConfig) -> Result<String, MainError> {
+    let (parser_config, telegrams) = generate(config)?;
+    serialize(&parser_config, &telegrams)
+}
+
+impl Generator {
+    fn next_telegrams(&mut self) -> Result<Vec<Telegram>, MainError> {
+        let date = Date::from_timestamp(self.timestamp)?;
+        if self.rng.next_f64() < self.config.event_probability {
+            self.add_event(&date)?;
+        }
+
+        let mut electricity = self.electricity(&date)?;
+        if !self.config.is_gas {
+            return Ok(vec![electricity]);
+        }
+
+        if self.config.is_recursive {
+            electricity.children.push(self.gas(&date, 1)?);
+            Ok(vec![electricity])
+        } else {
+            let gas = self.gas(&date, 0)?;
+            Ok(vec![electricity, gas])
+        }
+    }
+
+    fn add_event(&mut self, date: &Date) -> Result<(), MainError> {
+        let (severity, message) =
+            EVENT_MESSAGES[(self.rng.next_u64() % EVENT_MESSAGES.len() as u64) as usize];
+        // Events happened somewhere since the previous telegram
+        let seconds_ago = (self.rng.next_f64() * self.config.interval as f64) as i64;
+        self.events.push(Event {
+            id: self.next_event_id,
+            severity,
+            message,
+            date: date.checked_add(TimeDelta::seconds(-seconds_ago))?,
+        });
+        self.next_event_id += 1;
+        if self.events.len() > EVENT_MEMORY {
+            self.events.remove(0);
+        }
+        Ok(())
+    }
+
+    fn base(&self, date: &Date, information_type: InformationType, depth: u32) -> TelegramBase {
+        let mut eventlog = Vec::new();
+        // Only the outermost telegram carries the eventlog
+        if depth == 0 {
+            for event in &self.events {
+                eventlog.push(EventLogEntry {
+                    index: event.id,
+                    severity: event.severity,
+                    message: event.message.to_string(),
+                    date: event.date.clone(),
+                });
+            }
+        }
+
+        TelegramBase::new(
+            Marker(Some(depth)),
+            date.clone(),
+            eventlog,
+            information_type,
+            Marker(Some(depth)),
+        )
+    }
+
+    fn electricity(&mut self, date: &Date) -> Result<Telegram, MainError> {
+        let mut voltages = Vec::new();
+        let mut currents = Vec::new();
+        let mut powers = Vec::new();
+        let mut net_power = 0.0;
+
+        for _ in 0..3 {
+            let voltage = round(self.rng.range(225.0, 240.0), 2);
+            let current = round(self.rng.range(0.0, 9.9), 1);
+            // Roughly one in five phases is producing (solar panels)
+            let sign = if self.rng.next_f64() < 0.2 { -1.0 } else { 1.0 };
+            let power = round(sign * voltage * current / 1000.0, 3);
+            net_power += power;
+
+            voltages.push(Voltage(voltage));
+            currents.push(Current(current));
+            powers.push(Power(power));
+        }
+
+        // kW over the interval, in Wh
+        let energy_wh = (net_power.abs() * self.config.interval as f64 / 3.6).round() as u64;
+        if net_power >= 0.0 {
+            self.consumed_wh += energy_wh;
+        } else {
+            self.produced_wh += energy_wh;
+        }
+
+        Ok(Telegram::new(
+            self.base(date, InformationType::Electricity, 0),
+            TelegramData::Electricity {
+                voltages,
+                currents,
+                powers,
+                total_consumed: Energy(Decimal::new(self.consumed_wh as i64, 3).normalize()),
+                total_produced: Energy(Decimal::new(self.produced_wh as i64, 3).normalize()),
+                tariffs: None,
+                quality: None,
+            },
+        ))
+    }
+
+    fn gas(&mut self, date: &Date, depth: u32) -> Result<Telegram, MainError> {
+        // Gas is only used now and then (heating, showers, cooking)
+        if self.rng.next_f64() < 0.3 {
+            self.gas_liters += (self.rng.range(0.0, 0.5) * self.config.interval as f64) as u64;
+        }
+
+        Ok(Telegram::new(
+            self.base(date, InformationType::Gas, depth),
+            TelegramData::Gas {
+                total_gas_delivered: Volume(Decimal::new(self.gas_liters as i64, 3).normalize()),
+            },
+        ))
+    }
+}
+
+fn round(value: f64, decimals: i32) -> f64 {
+    let factor = 10f64.powi(decimals);
+    (value * factor).round() / factor
+}
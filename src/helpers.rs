@@ -1,254 +1,230 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::io::Read;
 
-use crate::bail;
+use chrono::DateTime;
 use itertools::Itertools;
 
 use tudelft_dsmr_output_generator::current_over_time::{CurrentData, CurrentOverTime};
 use tudelft_dsmr_output_generator::energy_over_time::{EnergyData, EnergyOverTime};
 use tudelft_dsmr_output_generator::gas_over_time::{GasData, GasOverTime};
 use tudelft_dsmr_output_generator::voltage_over_time::VoltageData;
+use tudelft_dsmr_output_generator::water_over_time::{WaterData, WaterOverTime};
 use tudelft_dsmr_output_generator::{GraphBuilder, Graphs, UnixTimeStamp};
 
+use crate::decimal::Decimal;
 use crate::error::MainError;
 use crate::telegram::*;
 
+/// The voltages that are plotted. The plot has three phases: missing phases are plotted as 0,
+/// phases beyond the third are left out. See [`voltage_phases`] for every phase.
 pub fn process_voltages(telegrams: &[Telegram]) -> Vec<VoltageData> {
-    let mut processed_map: HashMap<UnixTimeStamp, (f64, f64, f64)> = HashMap::new();
-    telegrams
-        .iter()
-        .filter_map(|t| {
-            let timestamp = match &t.base.date.value {
-                Some(Value::Date(date)) => date.timestamp,
-                _ => bail!("Invalid timestamp"),
-            };
-            match &t.data {
-                TelegramData::Electricity { voltages, .. } => match voltages {
-                    [TelegramContent {
-                        value: Some(Value::Float(p1)),
-                        ..
-                    }, TelegramContent {
-                        value: Some(Value::Float(p2)),
-                        ..
-                    }, TelegramContent {
-                        value: Some(Value::Float(p3)),
-                        ..
-                    }] => {
-                        if !processed_map.contains_key(&timestamp) {
-                            processed_map.insert(timestamp, (*p1, *p2, *p3));
-                            Some(VoltageData {
-                                timestamp: timestamp,
-                                phase_1: *p1,
-                                phase_2: *p2,
-                                phase_3: *p3,
-                            })
-                        } else {
-                            let mut new_value = processed_map
-                                .get(&timestamp)
-                                .unwrap_or_else(|| bail!("Could not unpack old_value"))
-                                .clone();
-                            if *p1 > new_value.0 {
-                                new_value.0 = *p1;
-                            }
-                            if *p2 > new_value.1 {
-                                new_value.1 = *p2;
-                            }
-                            if *p3 > new_value.2 {
-                                new_value.2 = *p3;
-                            }
-                            processed_map.insert(timestamp, new_value);
-                            Some(VoltageData {
-                                timestamp: timestamp,
-                                phase_1: new_value.0,
-                                phase_2: new_value.1,
-                                phase_3: new_value.2,
-                            })
-                        }
-                    }
-                    _ => Option::None,
-                },
-                _ => Option::None,
+    voltage_phases(telegrams)
+        .into_iter()
+        .map(|voltage| {
+            let (phase_1, phase_2, phase_3) = three_phases(&voltage.phases);
+            VoltageData {
+                timestamp: voltage.timestamp,
+                phase_1,
+                phase_2,
+                phase_3,
             }
         })
         .collect()
 }
 
 pub fn process_currents(telegrams: &[Telegram]) -> CurrentOverTime {
-    let mut processed_map: HashMap<UnixTimeStamp, (f64, f64, f64)> = HashMap::new();
-    let currents: Vec<CurrentData> = telegrams
-        .iter()
-        .filter_map(|t| {
-            let timestamp = match &t.base.date.value {
-                Some(Value::Date(date)) => date.timestamp,
-                _ => bail!("Invalid timestamp"),
-            };
-            match &t.data {
-                TelegramData::Electricity { currents, .. } => match currents {
-                    [TelegramContent {
-                        value: Some(Value::Float(p1)),
-                        ..
-                    }, TelegramContent {
-                        value: Some(Value::Float(p2)),
-                        ..
-                    }, TelegramContent {
-                        value: Some(Value::Float(p3)),
-                        ..
-                    }] => {
-                        if !processed_map.contains_key(&timestamp) {
-                            processed_map.insert(timestamp, (*p1, *p2, *p3));
-                            Some(CurrentData {
-                                timestamp: timestamp,
-                                phase_1: *p1,
-                                phase_2: *p2,
-                                phase_3: *p3,
-                            })
-                        } else {
-                            let mut new_value = processed_map
-                                .get(&timestamp)
-                                .unwrap_or_else(|| bail!("Could not unpack old_value"))
-                                .clone();
-                            if *p1 > new_value.0 {
-                                new_value.0 = *p1;
-                            }
-                            if *p2 > new_value.1 {
-                                new_value.1 = *p2;
-                            }
-                            if *p3 > new_value.2 {
-                                new_value.2 = *p3;
-                            }
-                            processed_map.insert(timestamp, new_value);
-                            Some(CurrentData {
-                                timestamp: timestamp,
-                                phase_1: new_value.0,
-                                phase_2: new_value.1,
-                                phase_3: new_value.2,
-                            })
-                        }
-                    }
-                    _ => Option::None,
-                },
-                _ => Option::None,
-            }
-        })
-        .collect();
-
     let mut current_over_time = CurrentOverTime::new();
-    for c in currents {
-        current_over_time.add(c);
+    for current in current_series(telegrams) {
+        current_over_time.add(current);
     }
     current_over_time
 }
 
-pub fn process_gas_data(telegrams: &[Telegram]) -> GasOverTime {
-    let mut processed_map: HashMap<UnixTimeStamp, f64> = HashMap::new();
-    let gas_pairs: Vec<(UnixTimeStamp, f64)> = telegrams
-        .iter()
-        .filter_map(|t| {
-            let timestamp = match &t.base.date.value {
-                Some(Value::Date(date)) => date.timestamp,
-                _ => bail!("Invalid timestamp"),
-            };
-            match &t.data {
-                TelegramData::Gas {
-                    total_gas_delivered,
-                } => match total_gas_delivered {
-                    TelegramContent {
-                        value: Some(Value::Float(gas)),
-                        ..
-                    } => {
-                        if !processed_map.contains_key(&timestamp) {
-                            processed_map.insert(timestamp, *gas);
-                            Some((timestamp, *gas))
-                        } else {
-                            let new_value = processed_map
-                                .get(&timestamp)
-                                .unwrap_or_else(|| bail!("Could not unpack old_value"))
-                                .clone();
-                            if new_value > *gas {
-                                processed_map.insert(timestamp, new_value);
-                                Some((timestamp, new_value))
-                            } else {
-                                processed_map.insert(timestamp, *gas);
-                                Some((timestamp, *gas))
-                            }
-                        }
-                    }
-                    _ => Option::None,
-                },
-                _ => Option::None,
+/// The currents that [`process_currents`] plots, one entry per timestamp.
+/// Like [`process_voltages`], this always has three phases, see [`current_phases`] for every phase.
+pub fn current_series(telegrams: &[Telegram]) -> Vec<CurrentData> {
+    current_phases(telegrams)
+        .into_iter()
+        .map(|current| {
+            let (phase_1, phase_2, phase_3) = three_phases(&current.phases);
+            CurrentData {
+                timestamp: current.timestamp,
+                phase_1,
+                phase_2,
+                phase_3,
             }
         })
-        .collect();
+        .collect()
+}
 
-    let mut gas_delta_over_time: GasOverTime = GasOverTime::new();
-    for (idx, (timestamp, current_gas)) in gas_pairs.iter().enumerate() {
-        if idx == 0 {
-            continue;
+/// The readings of every phase at one timestamp
+#[derive(Clone, Debug, PartialEq)]
+pub struct PhaseData {
+    pub timestamp: UnixTimeStamp,
+    /// One reading per phase, starting with phase 1
+    pub phases: Vec<f64>,
+}
+
+/// The voltage of every phase, one entry per timestamp
+pub fn voltage_phases(telegrams: &[Telegram]) -> Vec<PhaseData> {
+    phase_series(telegrams, |data| match data {
+        TelegramData::Electricity { voltages, .. } => Some(values(voltages)),
+        _ => None,
+    })
+}
+
+/// The current of every phase, one entry per timestamp
+pub fn current_phases(telegrams: &[Telegram]) -> Vec<PhaseData> {
+    phase_series(telegrams, |data| match data {
+        TelegramData::Electricity { currents, .. } => Some(values(currents)),
+        _ => None,
+    })
+}
+
+fn values<Q: Quantity>(quantities: &[Q]) -> Vec<f64> {
+    quantities.iter().map(|q| q.value()).collect()
+}
+
+/// One entry per timestamp, oldest first. Telegrams with the same timestamp are combined by
+/// taking the highest reading of every phase.
+fn phase_series(
+    telegrams: &[Telegram],
+    readings_of: impl Fn(&TelegramData) -> Option<Vec<f64>>,
+) -> Vec<PhaseData> {
+    let mut processed_map: BTreeMap<UnixTimeStamp, Vec<f64>> = BTreeMap::new();
+    for t in telegrams {
+        if let Some(readings) = readings_of(&t.data) {
+            let new_value: Vec<f64> = match processed_map.remove(&t.timestamp()) {
+                None => readings,
+                Some(old_value) => old_value
+                    .into_iter()
+                    .zip_longest(readings)
+                    .map(|pair| pair.reduce(f64::max))
+                    .collect(),
+            };
+            processed_map.insert(t.timestamp(), new_value);
         }
-        gas_delta_over_time.add(GasData {
-            timestamp: *timestamp,
-            gas_delta: *current_gas - gas_pairs[idx - 1].1,
-        });
     }
-    gas_delta_over_time
+
+    processed_map
+        .into_iter()
+        .map(|(timestamp, phases)| PhaseData { timestamp, phases })
+        .collect()
 }
 
-pub fn process_energy_data(telegrams: &[Telegram]) -> EnergyOverTime {
-    let mut processed_map: HashMap<UnixTimeStamp, (f64, f64)> = HashMap::new();
-    telegrams.iter().for_each(|t| {
-        let timestamp = match &t.base.date.value {
-            Some(Value::Date(date)) => date.timestamp,
-            _ => bail!("Invalid timestamp"),
-        };
-        match &t.data {
-            TelegramData::Electricity {
-                total_consumed,
-                total_produced,
-                ..
-            } => match (total_consumed, total_produced) {
-                (
-                    TelegramContent {
-                        value: Some(Value::Float(consumed)),
-                        ..
-                    },
-                    TelegramContent {
-                        value: Some(Value::Float(produced)),
-                        ..
-                    },
-                ) => {
-                    if !processed_map.contains_key(&timestamp) {
-                        processed_map.insert(timestamp, (*consumed, *produced));
-                    } else {
-                        let old_value = processed_map
-                            .get(&timestamp)
-                            .unwrap_or_else(|| bail!("Could not unpack old_value"))
-                            .clone();
-                        processed_map.insert(
-                            timestamp,
-                            (old_value.0 + *consumed, old_value.1 + *produced),
-                        );
-                    }
-                }
-                _ => (),
-            },
-            _ => (),
-        };
-    });
+fn three_phases(phases: &[f64]) -> (f64, f64, f64) {
+    let phase = |index: usize| phases.get(index).copied().unwrap_or(0.0);
+    (phase(0), phase(1), phase(2))
+}
 
-    let mut energy_pair_delta_over_time: EnergyOverTime = EnergyOverTime::new();
-    let mut energy_pair_vector: Vec<EnergyData> = Vec::new();
-    let total_pairs: Vec<(UnixTimeStamp, (f64, f64))> =
-        processed_map.into_iter().sorted_by_key(|p| p.0).collect();
-    for (idx, (timestamp, (consumed, produced))) in total_pairs.iter().enumerate() {
-        if idx == 0 {
-            continue;
+pub fn process_gas_data(telegrams: &[Telegram]) -> Result<GasOverTime, MainError> {
+    let mut gas_delta_over_time: GasOverTime = GasOverTime::new();
+    for gas in gas_series(telegrams)? {
+        gas_delta_over_time.add(gas);
+    }
+    Ok(gas_delta_over_time)
+}
+
+/// The gas usage between consecutive gas telegrams that [`process_gas_data`] plots
+pub fn gas_series(telegrams: &[Telegram]) -> Result<Vec<GasData>, MainError> {
+    let deltas = delivered_deltas(telegrams, |data| match data {
+        TelegramData::Gas {
+            total_gas_delivered,
+        } => Some(total_gas_delivered.0),
+        _ => None,
+    })?;
+    Ok(deltas
+        .into_iter()
+        .map(|(timestamp, gas_delta)| GasData {
+            timestamp,
+            gas_delta: gas_delta.to_f64(),
+        })
+        .collect())
+}
+
+pub fn process_water_data(telegrams: &[Telegram]) -> Result<WaterOverTime, MainError> {
+    let mut water_delta_over_time = WaterOverTime::new();
+    for water in water_series(telegrams)? {
+        water_delta_over_time.add(water);
+    }
+    Ok(water_delta_over_time)
+}
+
+/// The water usage between consecutive water telegrams that [`process_water_data`] plots,
+/// in whole liters. A counter that went down (e.g. a replaced meter) is not usage, so
+/// those deltas are left out.
+pub fn water_series(telegrams: &[Telegram]) -> Result<Vec<WaterData>, MainError> {
+    let deltas = delivered_deltas(telegrams, |data| match data {
+        TelegramData::Water {
+            total_water_delivered,
+        } => Some(total_water_delivered.0),
+        _ => None,
+    })?;
+    Ok(deltas
+        .into_iter()
+        .filter_map(|(timestamp, delta)| {
+            // m3 to liters, water readings have three decimals
+            let liters = u64::try_from(delta.rescale(3)?.mantissa()).ok()?;
+            Some(WaterData {
+                timestamp,
+                water_delta: liters,
+            })
+        })
+        .collect())
+}
+
+/// Heat delivered between two consecutive heat telegrams, see [`heat_series`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeatData {
+    /// GJ
+    pub heat_delta: Decimal,
+    pub timestamp: UnixTimeStamp,
+}
+
+/// The heat delivered between consecutive heat telegrams. There is no plot for heat.
+pub fn heat_series(telegrams: &[Telegram]) -> Result<Vec<HeatData>, MainError> {
+    let deltas = delivered_deltas(telegrams, |data| match data {
+        TelegramData::Heat {
+            total_heat_delivered,
+        } => Some(total_heat_delivered.0),
+        _ => None,
+    })?;
+    Ok(deltas
+        .into_iter()
+        .map(|(timestamp, heat_delta)| HeatData {
+            timestamp,
+            heat_delta,
+        })
+        .collect())
+}
+
+/// The increase of a cumulative counter between consecutive timestamps, oldest first. Of
+/// telegrams with the same timestamp, the highest reading is used.
+fn delivered_deltas(
+    telegrams: &[Telegram],
+    counter_of: impl Fn(&TelegramData) -> Option<Decimal>,
+) -> Result<Vec<(UnixTimeStamp, Decimal)>, MainError> {
+    let mut processed_map: BTreeMap<UnixTimeStamp, Decimal> = BTreeMap::new();
+    for t in telegrams {
+        if let Some(delivered) = counter_of(&t.data) {
+            let new_value = processed_map
+                .get(&t.timestamp())
+                .map_or(delivered, |old_value| (*old_value).max(delivered));
+            processed_map.insert(t.timestamp(), new_value);
         }
-        energy_pair_vector.push(EnergyData {
-            timestamp: *timestamp,
-            consumed: *consumed - total_pairs[idx - 1].1 .0,
-            produced: *produced - total_pairs[idx - 1].1 .1,
-        });
     }
+
+    processed_map
+        .into_iter()
+        .tuple_windows()
+        .map(|((_, previous), (timestamp, current))| Ok((timestamp, subtract(current, previous)?)))
+        .collect()
+}
+
+/// Plots the total energy, see [`tariff_series`] for the energy per tariff
+pub fn process_energy_data(telegrams: &[Telegram]) -> Result<EnergyOverTime, MainError> {
+    let mut energy_pair_delta_over_time: EnergyOverTime = EnergyOverTime::new();
+    let mut energy_pair_vector = energy_series(telegrams)?;
     for _ in 0..12 {
         if let Some(e) = energy_pair_vector.pop() {
             energy_pair_delta_over_time.add(e);
@@ -256,49 +232,264 @@ pub fn process_energy_data(telegrams: &[Telegram]) -> EnergyOverTime {
             break;
         }
     }
-    energy_pair_delta_over_time
+    Ok(energy_pair_delta_over_time)
+}
+
+/// The energy consumed and produced between every pair of consecutive timestamps, in
+/// chronological order. [`process_energy_data`] only plots the last 12 of these.
+pub fn energy_series(telegrams: &[Telegram]) -> Result<Vec<EnergyData>, MainError> {
+    let mut readings = Vec::new();
+    for t in telegrams {
+        if let TelegramData::Electricity {
+            total_consumed,
+            total_produced,
+            ..
+        } = &t.data
+        {
+            readings.push((t.timestamp(), [total_consumed.0, total_produced.0]));
+        }
+    }
+
+    Ok(counter_deltas(readings)?
+        .into_iter()
+        .map(|(timestamp, [consumed, produced])| EnergyData {
+            timestamp,
+            consumed: consumed.to_f64(),
+            produced: produced.to_f64(),
+        })
+        .collect())
 }
 
+/// Energy consumed and produced per tariff, see [`tariff_series`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TariffEnergyData {
+    pub timestamp: UnixTimeStamp,
+    /// kWh for tariff 1 (low) and tariff 2 (normal)
+    pub consumed: [Decimal; 2],
+    /// kWh for tariff 1 (low) and tariff 2 (normal)
+    pub produced: [Decimal; 2],
+    /// The tariff that is active at `timestamp`
+    pub active_tariff: Tariff,
+}
+
+/// Per tariff version of [`energy_series`], for the telegrams of dual-tariff meters.
+/// Telegrams without tariff counters are skipped.
+pub fn tariff_series(telegrams: &[Telegram]) -> Result<Vec<TariffEnergyData>, MainError> {
+    let mut readings = Vec::new();
+    let mut active_tariffs: HashMap<UnixTimeStamp, Tariff> = HashMap::new();
+    for t in telegrams {
+        if let TelegramData::Electricity {
+            tariffs: Some(tariffs),
+            ..
+        } = &t.data
+        {
+            let [consumed_1, consumed_2] = tariffs.consumed;
+            let [produced_1, produced_2] = tariffs.produced;
+            readings.push((
+                t.timestamp(),
+                [consumed_1.0, consumed_2.0, produced_1.0, produced_2.0],
+            ));
+            active_tariffs.insert(t.timestamp(), tariffs.active);
+        }
+    }
+
+    Ok(counter_deltas(readings)?
+        .into_iter()
+        .map(
+            |(timestamp, [consumed_1, consumed_2, produced_1, produced_2])| TariffEnergyData {
+                timestamp,
+                consumed: [consumed_1, consumed_2],
+                produced: [produced_1, produced_2],
+                active_tariff: active_tariffs[&timestamp],
+            },
+        )
+        .collect())
+}
+
+/// Adds up the cumulative counters of readings with the same timestamp (e.g. of multiple
+/// meters), then returns how much each counter increased between consecutive timestamps
+fn counter_deltas<const N: usize>(
+    readings: Vec<(UnixTimeStamp, [Decimal; N])>,
+) -> Result<Vec<(UnixTimeStamp, [Decimal; N])>, MainError> {
+    let mut processed_map: HashMap<UnixTimeStamp, [Decimal; N]> = HashMap::new();
+    for (timestamp, counters) in readings {
+        let total = processed_map.entry(timestamp).or_insert([Decimal::ZERO; N]);
+        for (total, counter) in total.iter_mut().zip(counters) {
+            *total = total
+                .checked_add(counter)
+                .ok_or_else(|| MainError::DecimalOverflow(format!("{total} + {counter}")))?;
+        }
+    }
+
+    processed_map
+        .into_iter()
+        .sorted_by_key(|p| p.0)
+        .tuple_windows()
+        .map(|((_, previous), (timestamp, current))| {
+            let mut delta = [Decimal::ZERO; N];
+            for ((delta, current), previous) in delta.iter_mut().zip(current).zip(previous) {
+                *delta = subtract(current, previous)?;
+            }
+            Ok((timestamp, delta))
+        })
+        .collect()
+}
+
+/// `current - previous`, failing rather than panicking if the result does not fit
+fn subtract(current: Decimal, previous: Decimal) -> Result<Decimal, MainError> {
+    current
+        .checked_sub(previous)
+        .ok_or_else(|| MainError::DecimalOverflow(format!("{current} - {previous}")))
+}
+
+/// Adds every event of the [`event_timeline`] to the event log once, oldest first
 pub fn process_event_logs(telegrams: &[Telegram], result: &mut Graphs) {
-    telegrams.iter().for_each(|t| {
-        for (id, _) in &t.base.eventlog_dates {
-            let (id, severity) = &t
-                .base
-                .eventlog_severities
-                .iter()
-                .find(|x| x.0 == *id)
-                .unwrap_or_else(|| bail!("Eventlog misses date"));
-            let (_, message) = &t
-                .base
-                .eventlog_messages
-                .iter()
-                .find(|x| x.0 == *id)
-                .unwrap_or_else(|| bail!("Eventlog misses message"));
-            match message {
-                TelegramContent {
-                    value: Some(Value::String(message)),
-                    ..
-                } => {
-                    match severity {
-                        TelegramContent {
-                            value: Some(Value::String(severity)),
-                            ..
-                        } => {
-                            if *severity == "H".to_string() {
-                                result.add_high_severity_event_log_message(decode_message(message));
-                            } else if *severity == "L".to_string() {
-                                result.add_low_severity_event_log_message(decode_message(message));
-                            } else {
-                                bail!("Unknown severity value")
-                            }
-                        }
-                        _ => bail!("Invalid severity found"),
-                    };
+    for event in event_timeline(telegrams) {
+        match event.severity {
+            Severity::High => result.add_high_severity_event_log_message(event.message),
+            Severity::Low => result.add_low_severity_event_log_message(event.message),
+        };
+    }
+}
+
+/// An eventlog entry and the telegrams that logged it, see [`event_timeline`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimelineEvent {
+    pub severity: Severity,
+    pub message: String,
+    /// When the event happened
+    pub date: Date,
+    /// Timestamp of the oldest telegram that logged the event
+    pub first_seen: UnixTimeStamp,
+    /// Timestamp of the newest telegram that logged the event
+    pub last_seen: UnixTimeStamp,
+}
+
+/// The eventlog entries of all telegrams, oldest first. Meters repeat their log in every
+/// telegram, so entries with the same date, severity and message are listed once.
+pub fn event_timeline(telegrams: &[Telegram]) -> Vec<TimelineEvent> {
+    let mut events: Vec<TimelineEvent> = Vec::new();
+    let mut seen: HashMap<(UnixTimeStamp, Severity, &str), usize> = HashMap::new();
+    for t in telegrams {
+        let timestamp = t.timestamp();
+        for entry in &t.base.eventlog {
+            let key = (entry.date.timestamp, entry.severity, entry.message.as_str());
+            match seen.get(&key) {
+                Some(&i) => {
+                    events[i].first_seen = events[i].first_seen.min(timestamp);
+                    events[i].last_seen = events[i].last_seen.max(timestamp);
+                }
+                None => {
+                    seen.insert(key, events.len());
+                    events.push(TimelineEvent {
+                        severity: entry.severity,
+                        message: entry.message.clone(),
+                        date: entry.date.clone(),
+                        first_seen: timestamp,
+                        last_seen: timestamp,
+                    });
                 }
-                _ => bail!("Invalid message found"),
             }
         }
-    });
+    }
+
+    // Stable, so events of the same moment keep the order they were logged in
+    events.sort_by_key(|event| event.date.timestamp);
+    events
+}
+
+/// A long power failure, see [`power_failure_timeline`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerFailureEvent {
+    /// When the power went out
+    pub start: UnixTimeStamp,
+    /// When the power came back
+    pub end: UnixTimeStamp,
+    /// Seconds
+    pub duration: u64,
+}
+
+impl PowerFailureEvent {
+    /// Text for the event log, e.g. `Long power failure of 240 s, ended 2023-07-05 13:26:41 UTC`
+    pub fn message(&self) -> String {
+        let end = DateTime::from_timestamp(self.end, 0)
+            .map(|end| end.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| self.end.to_string());
+        format!("Long power failure of {} s, ended {end}", self.duration)
+    }
+}
+
+/// The long power failures in the logs of all telegrams, oldest first. Meters repeat their
+/// log in every telegram, so a failure that is logged more than once is listed once.
+pub fn power_failure_timeline(telegrams: &[Telegram]) -> Vec<PowerFailureEvent> {
+    let mut events = Vec::new();
+    for t in telegrams {
+        if let TelegramData::Electricity {
+            quality: Some(quality),
+            ..
+        } = &t.data
+        {
+            for failure in &quality.failure_log {
+                events.push(PowerFailureEvent {
+                    start: failure.end.timestamp - failure.duration as UnixTimeStamp,
+                    end: failure.end.timestamp,
+                    duration: failure.duration,
+                });
+            }
+        }
+    }
+
+    events.sort_by_key(|event| (event.start, event.end));
+    events.dedup();
+    events
+}
+
+/// Adds the [`power_failure_timeline`] to the event log, as high severity messages
+pub fn process_power_failures(telegrams: &[Telegram], result: &mut Graphs) {
+    for event in power_failure_timeline(telegrams) {
+        result.add_high_severity_event_log_message(event.message());
+    }
+}
+
+/// The telegrams of one meter, see [`group_by_meter`]
+#[derive(Debug, PartialEq)]
+pub struct MeterTelegrams {
+    /// The equipment identifier, `None` for telegrams without one
+    pub equipment_id: Option<String>,
+    pub telegrams: Vec<Telegram>,
+}
+
+/// Splits telegrams by the meter that sent them, so the series of different meters do not get
+/// mixed. The telegrams of a meter keep their order, meters are sorted by equipment identifier
+/// with the telegrams without one first.
+pub fn group_by_meter(telegrams: Vec<Telegram>) -> Vec<MeterTelegrams> {
+    let mut meters: Vec<MeterTelegrams> = Vec::new();
+    for t in telegrams {
+        match meters
+            .iter_mut()
+            .find(|m| m.equipment_id == t.base.equipment_id)
+        {
+            Some(meter) => meter.telegrams.push(t),
+            None => meters.push(MeterTelegrams {
+                equipment_id: t.base.equipment_id.clone(),
+                telegrams: vec![t],
+            }),
+        }
+    }
+    meters.sort_by(|a, b| a.equipment_id.cmp(&b.equipment_id));
+    meters
+}
+
+/// Runs one of the helpers of this module for every meter, e.g.
+/// `per_meter(&meters, voltage_phases)`
+pub fn per_meter<T>(
+    meters: &[MeterTelegrams],
+    mut process: impl FnMut(&[Telegram]) -> T,
+) -> Vec<(Option<String>, T)> {
+    meters
+        .iter()
+        .map(|meter| (meter.equipment_id.clone(), process(&meter.telegrams)))
+        .collect()
 }
 
 pub fn read_from_stdin() -> Result<String, MainError> {
@@ -309,23 +500,29 @@ pub fn read_from_stdin() -> Result<String, MainError> {
     Ok(String::from_utf8_lossy(&input).to_string())
 }
 
-pub fn decode_message(message: &String) -> String {
+pub fn decode_message(message: &str) -> Result<String, MainError> {
+    let bad_message = || MainError::BadHexMessage(message.to_string());
+
     let mut result = "".to_string();
     let mut chars = message.chars();
     while let Some(ch) = chars.next() {
-        let x = ch
-            .to_digit(16)
-            .unwrap_or_else(|| bail!("Invalid character in message"));
-        if let Some(y) = chars.next() {
-            let y = y
-                .to_digit(16)
-                .unwrap_or_else(|| bail!("Invalid character in message"));
-            let ascii = char::from_u32(16 * x + y).unwrap_or_else(|| bail!("Invalid ASCII value"));
-            result.push(ascii);
-        } else {
-            bail!("Unaligned block found")
-        }
+        let x = ch.to_digit(16).ok_or_else(bad_message)?;
+        // An odd number of characters means we found an unaligned block
+        let y = chars
+            .next()
+            .and_then(|y| y.to_digit(16))
+            .ok_or_else(bad_message)?;
+        let ascii = char::from_u32(16 * x + y).ok_or_else(bad_message)?;
+        result.push(ascii);
     }
 
-    result
+    Ok(result)
+}
+
+/// Inverse of [`decode_message`]: hex encodes every character of an ASCII message
+pub fn encode_message(message: &str) -> Result<String, MainError> {
+    if !message.is_ascii() {
+        return Err(MainError::BadHexMessage(message.to_string()));
+    }
+    Ok(message.bytes().map(|b| format!("{b:02x}")).collect())
 }
@@ -1,6 +1,16 @@
+pub mod checksum;
+pub mod cli;
+pub mod decimal;
+pub mod diagnostic;
 pub mod error;
+pub mod export;
+pub mod generator;
 pub mod helpers;
+pub mod obis;
 pub mod parser;
+pub mod registry;
 pub mod runner;
+pub mod serializer;
+pub mod stream;
 pub mod telegram;
 pub mod traits;
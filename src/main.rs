@@ -1,13 +1,14 @@
-use dsmr_assignment::error::MainError;
-use dsmr_assignment::runner::run;
+use std::process::ExitCode;
 
-fn main() -> Result<(), MainError> {
-    #[cfg(test)]
-    {
-        run(true)
-    }
-    #[cfg(not(test))]
-    {
-        run(false)
-    }
+use dsmr_assignment::cli::{execute, exit_code, parse_args, Command};
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    exit_code(parse_args(&args).and_then(|command| {
+        if let Command::Report { out: Some(out), .. } = &command {
+            // The graph library only takes its output folder from the environment
+            std::env::set_var("DSMR_OUTPUT_PATH", out);
+        }
+        execute(command)
+    }))
 }
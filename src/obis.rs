@@ -0,0 +1,493 @@
+//! # OBIS reference codes
+//! Production meters identify their values with OBIS codes, e.g. `1-0:32.7.0(230.1*V)`,
+//! instead of the simplified ids of the assignment, e.g. `7.1.1#(0230.1*V)`.
+//!
+//! With the OBIS dialect, lines with an OBIS code are mapped onto the content types and
+//! simplified ids in [`OBIS_CODES`], so the rest of the crate cannot tell the difference.
+//! Lines without an OBIS equivalent (`START`, `END`, eventlogs and the information type)
+//! keep using their simplified ids. The power a phase produces is stored as negative power of
+//! that phase, and the entries of the power failure log ([`FAILURE_LOG_CODE`]) as power failure
+//! dates and durations. Lines with one of the [`SKIPPED_CODES`] are left out.
+//!
+//! Only the lines are translated, telegrams are still framed like the assignment: a `/v12\+gro`
+//! style header, `START` and `END` lines and one meter per telegram. Raw P1 output, which starts
+//! with a `/XXX5` identification line, ends with `!CRC` and has the electricity and gas readings
+//! in one telegram, cannot be parsed and has to be converted first.
+use crate::decimal::Decimal;
+use crate::error::{parse_error, MainError};
+use crate::parser::parse_unit;
+use crate::registry::spec_for_type;
+use crate::telegram::*;
+
+/// How the ids of the lines in an input are written
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Dialect {
+    /// Only the simplified ids of the assignment
+    #[default]
+    Simplified,
+    /// OBIS codes where they exist, simplified ids for everything else
+    Obis,
+}
+
+impl TryFrom<&str> for Dialect {
+    type Error = MainError;
+
+    fn try_from(dialect: &str) -> Result<Self, Self::Error> {
+        match dialect {
+            "simplified" => Ok(Dialect::Simplified),
+            "obis" => Ok(Dialect::Obis),
+            _ => Err(MainError::UsageError(format!("Unknown dialect {dialect}"))),
+        }
+    }
+}
+
+pub struct ObisCode {
+    /// The code as written in the telegram, e.g. `1-0:32.7.0`
+    pub code: &'static str,
+    pub content_type: TelegramContentType,
+    /// The simplified id the value is stored under
+    pub id: (u32, u32, Option<u32>),
+    /// Digits before and after the decimal point of a numeric value, `None` for dates and texts
+    pub digits: Option<(usize, usize)>,
+    /// Whether the value is stored negated, for power that is delivered to the grid
+    pub is_negated: bool,
+}
+
+const fn obis(
+    code: &'static str,
+    content_type: TelegramContentType,
+    id: (u32, u32, Option<u32>),
+    digits: Option<(usize, usize)>,
+) -> ObisCode {
+    ObisCode {
+        code,
+        content_type,
+        id,
+        digits,
+        is_negated: false,
+    }
+}
+
+/// The power a phase delivers to the grid, which is negative power of that phase
+const fn produced_power(code: &'static str, phase: u32) -> ObisCode {
+    ObisCode {
+        code,
+        content_type: TelegramContentType::Power,
+        id: (7, 3, Some(phase)),
+        digits: Some((2, 3)),
+        is_negated: true,
+    }
+}
+
+/// The OBIS codes of DSMR 5 that have an equivalent in the simplified format
+pub static OBIS_CODES: [ObisCode; 31] = [
+    obis("0-0:1.0.0", TelegramContentType::Date, (2, 1, None), None),
+    obis(
+        "0-0:96.1.1",
+        TelegramContentType::EquipmentId,
+        (2, 2, None),
+        None,
+    ),
+    obis(
+        "0-1:96.1.0",
+        TelegramContentType::EquipmentId,
+        (2, 2, None),
+        None,
+    ),
+    obis(
+        "1-0:32.7.0",
+        TelegramContentType::Voltage,
+        (7, 1, Some(1)),
+        Some((3, 1)),
+    ),
+    obis(
+        "1-0:52.7.0",
+        TelegramContentType::Voltage,
+        (7, 1, Some(2)),
+        Some((3, 1)),
+    ),
+    obis(
+        "1-0:72.7.0",
+        TelegramContentType::Voltage,
+        (7, 1, Some(3)),
+        Some((3, 1)),
+    ),
+    obis(
+        "1-0:31.7.0",
+        TelegramContentType::Current,
+        (7, 2, Some(1)),
+        Some((3, 0)),
+    ),
+    obis(
+        "1-0:51.7.0",
+        TelegramContentType::Current,
+        (7, 2, Some(2)),
+        Some((3, 0)),
+    ),
+    obis(
+        "1-0:71.7.0",
+        TelegramContentType::Current,
+        (7, 2, Some(3)),
+        Some((3, 0)),
+    ),
+    obis(
+        "1-0:21.7.0",
+        TelegramContentType::Power,
+        (7, 3, Some(1)),
+        Some((2, 3)),
+    ),
+    obis(
+        "1-0:41.7.0",
+        TelegramContentType::Power,
+        (7, 3, Some(2)),
+        Some((2, 3)),
+    ),
+    obis(
+        "1-0:61.7.0",
+        TelegramContentType::Power,
+        (7, 3, Some(3)),
+        Some((2, 3)),
+    ),
+    produced_power("1-0:22.7.0", 1),
+    produced_power("1-0:42.7.0", 2),
+    produced_power("1-0:62.7.0", 3),
+    obis(
+        "1-0:1.8.0",
+        TelegramContentType::TotalConsumed,
+        (7, 4, Some(1)),
+        Some((6, 3)),
+    ),
+    obis(
+        "1-0:2.8.0",
+        TelegramContentType::TotalProduced,
+        (7, 4, Some(2)),
+        Some((6, 3)),
+    ),
+    obis(
+        "1-0:1.8.1",
+        TelegramContentType::TariffConsumed,
+        (7, 5, Some(1)),
+        Some((6, 3)),
+    ),
+    obis(
+        "1-0:1.8.2",
+        TelegramContentType::TariffConsumed,
+        (7, 5, Some(2)),
+        Some((6, 3)),
+    ),
+    obis(
+        "1-0:2.8.1",
+        TelegramContentType::TariffProduced,
+        (7, 6, Some(1)),
+        Some((6, 3)),
+    ),
+    obis(
+        "1-0:2.8.2",
+        TelegramContentType::TariffProduced,
+        (7, 6, Some(2)),
+        Some((6, 3)),
+    ),
+    obis(
+        "0-0:96.14.0",
+        TelegramContentType::TariffIndicator,
+        (7, 7, None),
+        None,
+    ),
+    obis(
+        "0-0:96.7.21",
+        TelegramContentType::PowerFailures,
+        (8, 1, None),
+        Some((5, 0)),
+    ),
+    obis(
+        "0-0:96.7.9",
+        TelegramContentType::LongPowerFailures,
+        (8, 2, None),
+        Some((5, 0)),
+    ),
+    obis(
+        "1-0:32.32.0",
+        TelegramContentType::VoltageSags,
+        (8, 5, Some(1)),
+        Some((5, 0)),
+    ),
+    obis(
+        "1-0:52.32.0",
+        TelegramContentType::VoltageSags,
+        (8, 5, Some(2)),
+        Some((5, 0)),
+    ),
+    obis(
+        "1-0:72.32.0",
+        TelegramContentType::VoltageSags,
+        (8, 5, Some(3)),
+        Some((5, 0)),
+    ),
+    obis(
+        "1-0:32.36.0",
+        TelegramContentType::VoltageSwells,
+        (8, 6, Some(1)),
+        Some((5, 0)),
+    ),
+    obis(
+        "1-0:52.36.0",
+        TelegramContentType::VoltageSwells,
+        (8, 6, Some(2)),
+        Some((5, 0)),
+    ),
+    obis(
+        "1-0:72.36.0",
+        TelegramContentType::VoltageSwells,
+        (8, 6, Some(3)),
+        Some((5, 0)),
+    ),
+    obis(
+        "0-1:24.2.1",
+        TelegramContentType::GasTotalDelivered,
+        (5, 2, None),
+        Some((5, 3)),
+    ),
+];
+
+/// The OBIS codes of DSMR 5 without an equivalent in the simplified format: the version of the
+/// P1 output, the total power (the power per phase is kept), the text message and the device
+/// type of the gas meter
+pub static SKIPPED_CODES: [&str; 5] = [
+    "1-3:0.2.8",
+    "1-0:1.7.0",
+    "1-0:2.7.0",
+    "0-0:96.13.0",
+    "0-1:24.1.0",
+];
+
+/// The power failure log, e.g. `1-0:99.97.0(1)(0-0:96.7.19)(230705151004S)(0000000301*s)`:
+/// the number of entries and the code of the failures it logs, followed by the end and the
+/// duration of every entry
+pub const FAILURE_LOG_CODE: &str = "1-0:99.97.0";
+
+pub fn lookup(code: &str) -> Option<&'static ObisCode> {
+    OBIS_CODES.iter().find(|obis| obis.code == code)
+}
+
+/// Whether [`parse_obis_contents`] understands the code
+pub fn is_known(code: &str) -> bool {
+    lookup(code).is_some() || code == FAILURE_LOG_CODE || SKIPPED_CODES.contains(&code)
+}
+
+/// Whether the line starts with an OBIS code (`A-B:C.D.E`) rather than a simplified id
+pub fn is_obis_line(line: &str) -> bool {
+    line.split_once('(')
+        .is_some_and(|(code, _)| code.contains('-') && code.contains(':'))
+}
+
+/// Parses a line with an OBIS code, e.g. `1-0:32.7.0(230.1*V)`.
+/// The gas reading also carries the time it was taken, `0-1:24.2.1(230705150000S)(12345.678*m3)`,
+/// of which only the reading itself is kept.
+///
+/// Only for the codes in [`OBIS_CODES`], which map onto a single content. Use
+/// [`parse_obis_contents`] for any line.
+pub fn parse_obis_line(line: &str) -> Result<TelegramContent, MainError> {
+    parse_obis_line_with(line, YearPivot::default())
+}
+
+/// Parses a line with an OBIS code into the contents it maps onto: none for the
+/// [`SKIPPED_CODES`], a date and a duration for every entry of the power failure log, and a
+/// single content for the codes in [`OBIS_CODES`]
+pub fn parse_obis_contents(line: &str) -> Result<Vec<TelegramContent>, MainError> {
+    parse_obis_contents_with(line, YearPivot::default())
+}
+
+/// Like [`parse_obis_contents`], expanding the two digit years of timestamps with `year_pivot`
+pub fn parse_obis_contents_with(
+    line: &str,
+    year_pivot: YearPivot,
+) -> Result<Vec<TelegramContent>, MainError> {
+    let (code, values) = split_line(line)?;
+    if SKIPPED_CODES.contains(&code) {
+        return Ok(Vec::new());
+    }
+    if code == FAILURE_LOG_CODE {
+        return parse_failure_log(values, year_pivot);
+    }
+    Ok(vec![parse_obis_line_with(line, year_pivot)?])
+}
+
+/// Like [`parse_obis_line`], expanding the two digit years of timestamps with `year_pivot`
+pub fn parse_obis_line_with(
+    line: &str,
+    year_pivot: YearPivot,
+) -> Result<TelegramContent, MainError> {
+    let (code, values) = split_line(line)?;
+    let obis = lookup(code).ok_or_else(|| {
+        if is_known(code) {
+            parse_error(&format!(
+                "OBIS code {code} does not map onto a single value"
+            ))
+        } else {
+            parse_error(&format!("Unknown OBIS code: {code}"))
+        }
+    })?;
+
+    let value_part = values.rsplit(")(").next().unwrap_or(values);
+
+    let (value_str, unit) = match value_part.split_once('*') {
+        Some((value, unit)) => (value, Some(parse_unit(unit)?)),
+        None => (value_part, None),
+    };
+
+    let value = match obis.digits {
+        Some(digits) => {
+            check_digits(value_str, digits)?;
+            let mut value: Decimal = value_str
+                .parse()
+                .map_err(|_| parse_error("Invalid float value"))?;
+            if obis.is_negated {
+                value = value
+                    .checked_neg()
+                    .ok_or_else(|| MainError::DecimalOverflow(format!("-{value}")))?;
+            }
+            Value::Number(value)
+        }
+        None if obis.content_type == TelegramContentType::Date => {
+            Value::Date(parse_obis_date(value_str, year_pivot)?)
+        }
+        None => {
+            if let Some(spec) = spec_for_type(&obis.content_type) {
+                spec.check_format(value_str)?;
+            }
+            Value::String(value_str.to_string())
+        }
+    };
+
+    let content = TelegramContent::new_value(obis.content_type.clone(), obis.id, value, unit);
+    content.check()?;
+    Ok(content)
+}
+
+/// Splits a line into its code and its values, without the outer parentheses
+fn split_line(line: &str) -> Result<(&str, &str), MainError> {
+    let (code, values) = line
+        .split_once('(')
+        .ok_or_else(|| parse_error("Invalid line format: missing parentheses"))?;
+    let values = values
+        .strip_suffix(')')
+        .ok_or_else(|| parse_error("Invalid line format: missing parentheses"))?;
+    Ok((code, values))
+}
+
+/// The entries of the power failure log, numbered from 1 like their simplified ids
+fn parse_failure_log(
+    values: &str,
+    year_pivot: YearPivot,
+) -> Result<Vec<TelegramContent>, MainError> {
+    let mut values = values.split(")(");
+    let count = match values.next() {
+        Some("") | None => 0,
+        Some(count) => count
+            .parse::<usize>()
+            .map_err(|_| parse_error("Invalid power failure count"))?,
+    };
+    if values.next() != Some("0-0:96.7.19") {
+        return Err(parse_error("Power failure log must log 0-0:96.7.19"));
+    }
+    let entries: Vec<&str> = values.collect();
+    if entries.len() != 2 * count {
+        return Err(parse_error(&format!(
+            "Expected {count} entries in the power failure log"
+        )));
+    }
+
+    let mut contents = Vec::new();
+    for (index, entry) in (1..).zip(entries.chunks(2)) {
+        let (duration, unit) = entry[1]
+            .split_once('*')
+            .ok_or_else(|| parse_error("Missing unit of power failure duration"))?;
+        check_digits(duration, (10, 0))?;
+        let duration = duration
+            .parse()
+            .map_err(|_| parse_error("Invalid float value"))?;
+
+        for content in [
+            TelegramContent::new_value(
+                TelegramContentType::PowerFailureDate,
+                (8, 3, Some(index)),
+                Value::Date(parse_obis_date(entry[0], year_pivot)?),
+                None,
+            ),
+            TelegramContent::new_value(
+                TelegramContentType::PowerFailureDuration,
+                (8, 4, Some(index)),
+                Value::Number(duration),
+                Some(parse_unit(unit)?),
+            ),
+        ] {
+            content.check()?;
+            contents.push(content);
+        }
+    }
+    Ok(contents)
+}
+
+/// Adds a content of an OBIS line to the contents of its telegram. The power a phase consumes
+/// and the power it produces are on separate lines, they are added up into the net power of
+/// the phase.
+pub(crate) fn push_content(
+    contents: &mut Vec<TelegramContent>,
+    content: TelegramContent,
+) -> Result<(), MainError> {
+    let phase_power = contents.iter_mut().find(|power| {
+        power.telegram_content_type == TelegramContentType::Power
+            && content.telegram_content_type == TelegramContentType::Power
+            && power.id == content.id
+    });
+    match phase_power {
+        Some(power) => {
+            let (a, b) = (power.number()?, content.number()?);
+            let net = a
+                .checked_add(b)
+                .ok_or_else(|| MainError::DecimalOverflow(format!("{a} + {b}")))?;
+            power.value = Some(Value::Number(net));
+        }
+        None => contents.push(content),
+    }
+    Ok(())
+}
+
+/// Parses an OBIS timestamp, `YYMMDDhhmmssX` where `X` is `S` (summer) or `W` (winter)
+pub fn parse_obis_date(date_str: &str, year_pivot: YearPivot) -> Result<Date, MainError> {
+    let (digits, dst) = match date_str.split_at_checked(12) {
+        Some((digits, "S")) => (digits, true),
+        Some((digits, "W")) => (digits, false),
+        _ => return Err(parse_error("Invalid OBIS timestamp")),
+    };
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(parse_error("Invalid OBIS timestamp"));
+    }
+
+    let field = |index: usize| digits[index..index + 2].parse::<u8>().unwrap_or_default();
+    Date::new(
+        year_pivot.expand(field(0)),
+        field(2),
+        field(4),
+        field(6),
+        field(8),
+        field(10),
+        dst,
+    )
+}
+
+fn check_digits(value: &str, (before, after): (usize, usize)) -> Result<(), MainError> {
+    let (integer, fraction) = value.split_once('.').unwrap_or((value, ""));
+    if integer.len() != before
+        || fraction.len() != after
+        || !integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(parse_error(&format!(
+            "Expected a value with {before} digits before and {after} after the decimal point"
+        )));
+    }
+    Ok(())
+}
@@ -1,6 +1,11 @@
-use crate::traits::validatable::Validatable;
+use std::collections::{BTreeMap, BTreeSet};
 
-use crate::error::{parse_error, MainError};
+use crate::checksum::{crc16_update, is_footer, parse_footer, Checksum, ChecksumPolicy};
+use crate::diagnostic::ParseDiagnostic;
+use crate::error::{diagnostic, parse_error, MainError};
+use crate::helpers::decode_message;
+use crate::obis::{self, is_obis_line, parse_obis_contents_with, Dialect};
+use crate::registry::{spec_for_id, ContentSpec, ValueKind};
 use crate::telegram::*;
 
 #[derive(Clone)]
@@ -8,6 +13,27 @@ pub struct ParserConfig {
     pub version: (u32, u32),
     pub is_gas: bool,
     pub is_recursive: bool,
+    /// Water and heat telegrams are allowed, with the `u` (utilities) extension
+    pub is_utility: bool,
+    /// Skip invalid lines and telegrams instead of aborting, see [`parse_lenient`]
+    pub is_lenient: bool,
+    /// What to do with checksum footers, see [`crate::checksum`]
+    pub checksum: ChecksumPolicy,
+    /// How the ids of the lines are written, see [`crate::obis`]
+    pub dialect: Dialect,
+    /// How two digit years in dates are expanded
+    pub year_pivot: YearPivot,
+}
+
+/// Settings of the parser that are picked by the caller, rather than by the header
+#[derive(Clone, Debug, Default)]
+pub struct ParseOptions {
+    /// See [`parse_lenient`]
+    pub lenient: bool,
+    pub checksum: ChecksumPolicy,
+    /// Overrides the dialect of the header
+    pub dialect: Option<Dialect>,
+    pub year_pivot: YearPivot,
 }
 
 impl ParserConfig {
@@ -27,11 +53,25 @@ impl ParserConfig {
         }
 
         Ok(Self {
-            version: version,
-            is_gas: is_gas,
-            is_recursive: is_recursive,
+            version,
+            is_gas,
+            is_recursive,
+            is_utility: false,
+            is_lenient: false,
+            checksum: ChecksumPolicy::default(),
+            dialect: Dialect::default(),
+            year_pivot: YearPivot::default(),
         })
     }
+
+    /// Applies the settings of the caller to the config read from a header
+    pub fn with_options(mut self, options: &ParseOptions) -> Self {
+        self.is_lenient = options.lenient;
+        self.checksum = options.checksum;
+        self.dialect = options.dialect.unwrap_or(self.dialect);
+        self.year_pivot = options.year_pivot;
+        self
+    }
 }
 
 pub fn parse_header(line: &str) -> Result<ParserConfig, MainError> {
@@ -71,105 +111,354 @@ pub fn parse_header(line: &str) -> Result<ParserConfig, MainError> {
         return ParserConfig::new(version, false, false);
     }
 
-    let (is_gas, is_recursive) = match parts[1] {
-        "g" => (true, false),
-        "r" => (false, true),
-        "gr" | "rg" => (true, true),
-        _ => return Err(parse_error("Header contains invalid extensions")),
-    };
+    // Every extension is a single letter, which may appear only once
+    let extensions = parts[1];
+    let has = |extension: char| extensions.contains(extension);
+    if extensions.is_empty()
+        || !extensions
+            .chars()
+            .all(|c| matches!(c, 'g' | 'r' | 'o' | 'u'))
+        || extensions
+            .chars()
+            .any(|c| extensions.matches(c).count() > 1)
+    {
+        return Err(parse_error("Header contains invalid extensions"));
+    }
 
-    ParserConfig::new(version, is_gas, is_recursive)
+    let mut config = ParserConfig::new(version, has('g'), has('r'))?;
+    config.is_utility = has('u');
+    if has('o') {
+        config.dialect = Dialect::Obis;
+    }
+    Ok(config)
 }
 
 pub fn parse(input: &str) -> Result<Vec<Telegram>, MainError> {
-    let lines: Vec<&str> = input.lines().collect();
-    if lines.is_empty() {
-        return Err(parse_error(
-            "Input is empty, did you connect your keyboard?",
-        ));
-    }
-    let mut config: Option<ParserConfig> = Option::None;
-    let mut temporary_stack: Vec<Vec<TelegramContent>> = Vec::new();
+    let (telegrams, _) = parse_tree_with(input, &ParseOptions::default())?;
+    Ok(flatten_reversed(telegrams))
+}
+
+/// Like [`parse`], but nested (recursive) telegrams are kept inside the `children` of the
+/// telegram they belong to. Only the outermost telegrams are returned, in input order.
+pub fn parse_tree(input: &str) -> Result<Vec<Telegram>, MainError> {
+    let (telegrams, _) = parse_tree_with(input, &ParseOptions::default())?;
+    Ok(telegrams)
+}
+
+/// Like [`parse`], but a line or telegram that fails to parse does not abort the whole input.
+/// The telegrams that were being built are dropped, parsing resumes at the next `START` line,
+/// and the failure is returned as a diagnostic next to the telegrams that did parse.
+///
+/// Only an invalid (or missing) header is still fatal, as nothing can be parsed without it.
+pub fn parse_lenient(input: &str) -> Result<(Vec<Telegram>, Vec<ParseDiagnostic>), MainError> {
+    let options = ParseOptions {
+        lenient: true,
+        ..ParseOptions::default()
+    };
+    let (telegrams, diagnostics) = parse_tree_with(input, &options)?;
+    Ok((flatten_reversed(telegrams), diagnostics))
+}
+
+/// Flattens trees of telegrams into the order [`parse`] has always returned them in:
+/// reversed order of completion, so parents come before their children.
+fn flatten_reversed(telegrams: Vec<Telegram>) -> Vec<Telegram> {
+    let mut flattened: Vec<Telegram> = telegrams.into_iter().flat_map(Telegram::flatten).collect();
+    flattened.reverse();
+    flattened
+}
+
+/// Like [`parse_tree`], but with the settings of the caller. Diagnostics are only collected
+/// when parsing leniently, otherwise the first failure is returned.
+pub fn parse_tree_with(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<Telegram>, Vec<ParseDiagnostic>), MainError> {
+    let (_, telegrams, diagnostics) = parse_tree_and_header(input, options)?;
+    Ok((telegrams, diagnostics))
+}
+
+/// Like [`parse_tree_with`], but also returns the config of the header, with `options` applied
+pub fn parse_tree_and_header(
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(ParserConfig, Vec<Telegram>, Vec<ParseDiagnostic>), MainError> {
+    // Keep the line endings, as they are part of the checksum
+    let mut lines = input.split_inclusive('\n').enumerate();
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| parse_error("Input is empty, did you connect your keyboard?"))?;
+    let header = strip_line_ending(header);
+    let config = parse_header(header)
+        .map_err(|e| diagnostic(1, header, e))?
+        .with_options(options);
+
+    let mut state = ParseState::default();
     let mut completed_stack: Vec<Telegram> = Vec::new();
+    let mut diagnostics: Vec<ParseDiagnostic> = Vec::new();
+    let mut resynchronising = false;
+    // Line number and text of the START line of every open telegram
+    let mut open_starts: Vec<(usize, &str)> = Vec::new();
+    // Line number and text of the END line of the telegram that is waiting for its footer
+    let mut footer_pending = (0, "");
 
     // Parse all lines into telegram contents
-    for (index, line) in lines.into_iter().enumerate() {
-        // Parse header
-        if index == 0 {
-            config = Some(parse_header(line)?);
-            continue;
+    for (index, raw) in lines {
+        let line = strip_line_ending(raw);
+
+        if resynchronising {
+            match parse_line(line) {
+                Ok(content) if content.telegram_content_type == TelegramContentType::Start => {
+                    resynchronising = false;
+                }
+                _ => continue,
+            }
         }
 
-        if line.trim().is_empty() {
+        let open = state.open_telegrams();
+        if let Err(e) = process_line(line, raw, &config, &mut state, &mut completed_stack) {
+            if !config.is_lenient {
+                return Err(diagnostic(index + 1, line, e));
+            }
+            diagnostics.push(ParseDiagnostic::new(index + 1, line, e));
+            state.reset();
+            open_starts.clear();
+            resynchronising = true;
             continue;
         }
+        if state.open_telegrams() > open {
+            open_starts.push((index + 1, line));
+        } else if state.open_telegrams() < open {
+            open_starts.pop();
+        }
+        if state.open_telegrams() == 0 && open > 0 {
+            footer_pending = (index + 1, line);
+        }
+    }
+    if let Err(e) = state.finish(&config, &mut completed_stack) {
+        let (line_number, line) = footer_pending;
+        if !config.is_lenient {
+            return Err(diagnostic(line_number, line, e));
+        }
+        diagnostics.push(ParseDiagnostic::new(line_number, line, e));
+    }
+
+    // Telegrams without an END line are skipped as well
+    if config.is_lenient {
+        for (line_number, line) in open_starts {
+            diagnostics.push(ParseDiagnostic::new(
+                line_number,
+                line,
+                parse_error("Telegram is not terminated by an END line"),
+            ));
+        }
+    }
+
+    Ok((config, completed_stack, diagnostics))
+}
+
+pub(crate) fn strip_line_ending(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/// A telegram of which the `END` line has not come in yet
+#[derive(Default)]
+pub(crate) struct PendingTelegram {
+    contents: Vec<TelegramContent>,
+    children: Vec<Telegram>,
+    /// Checksum of the lines so far, only kept for outermost telegrams
+    crc: u16,
+}
+
+/// Telegrams that are still being parsed
+#[derive(Default)]
+pub(crate) struct ParseState {
+    temporary_stack: Vec<PendingTelegram>,
+    /// Outermost telegram of which the `END` line came in, together with its checksum.
+    /// It is held back until we know whether a checksum footer follows.
+    awaiting_footer: Option<(Telegram, u16)>,
+}
+
+impl ParseState {
+    /// Drops all telegrams that are not complete
+    pub(crate) fn reset(&mut self) {
+        self.temporary_stack.clear();
+        self.awaiting_footer = None;
+    }
+
+    /// Number of telegrams of which the `END` line has not come in yet
+    pub(crate) fn open_telegrams(&self) -> usize {
+        self.temporary_stack.len()
+    }
+
+    /// Completes the telegram that is waiting for a footer, at the end of the input.
+    /// Under [`ChecksumPolicy::Reject`] that telegram has no footer, which is an error.
+    pub(crate) fn finish(
+        &mut self,
+        config: &ParserConfig,
+        completed_stack: &mut Vec<Telegram>,
+    ) -> Result<(), MainError> {
+        if let Some((telegram, _)) = self.awaiting_footer.take() {
+            if config.checksum == ChecksumPolicy::Reject {
+                return Err(parse_error("Telegram is not followed by a checksum footer"));
+            }
+            completed_stack.push(telegram);
+        }
+        Ok(())
+    }
+}
+
+/// Parses a single line (without its line ending) and moves it onto the stack of the telegram
+/// it belongs to. `raw` is the line as it was received, which is needed for checksums.
+/// Completed telegrams are added to the children of their parent, or to `completed_stack`
+/// if they are not nested.
+pub(crate) fn process_line(
+    line: &str,
+    raw: &str,
+    config: &ParserConfig,
+    state: &mut ParseState,
+    completed_stack: &mut Vec<Telegram>,
+) -> Result<(), MainError> {
+    let verify = config.checksum != ChecksumPolicy::Ignore;
+    let temporary_stack = &mut state.temporary_stack;
+
+    if line.trim().is_empty() {
+        if let Some(root) = temporary_stack.first_mut().filter(|_| verify) {
+            root.crc = crc16_update(root.crc, raw.as_bytes());
+        }
+        return Ok(());
+    }
+
+    if let Some((mut telegram, crc)) = state.awaiting_footer.take() {
+        if is_footer(line) {
+            let checksum = Checksum {
+                expected: parse_footer(line)?,
+                computed: crc16_update(crc, b"!"),
+            };
+            if config.checksum == ChecksumPolicy::Reject {
+                checksum.check()?;
+            }
+            telegram.checksum = Some(checksum);
+            completed_stack.push(telegram);
+            return Ok(());
+        }
+        if config.checksum == ChecksumPolicy::Reject {
+            return Err(parse_error("Telegram is not followed by a checksum footer"));
+        }
+        completed_stack.push(telegram);
+    }
+
+    if is_footer(line) {
+        parse_footer(line)?;
+        if verify || !temporary_stack.is_empty() {
+            return Err(parse_error(
+                "Checksum footer must follow the END line of an outermost telegram",
+            ));
+        }
+        return Ok(());
+    }
+
+    let contents = match config.dialect {
+        Dialect::Obis if is_obis_line(line) => parse_obis_contents_with(line, config.year_pivot)?,
+        _ => vec![parse_line_with(line, config.year_pivot)?],
+    };
+    if contents
+        .first()
+        .is_some_and(|content| content.telegram_content_type == TelegramContentType::Start)
+    {
+        temporary_stack.push(PendingTelegram::default());
+    }
+    if let Some(root) = temporary_stack.first_mut().filter(|_| verify) {
+        root.crc = crc16_update(root.crc, raw.as_bytes());
+    }
 
-        match parse_line(line) {
-            Ok(content) => {
-                match content.telegram_content_type {
-                    TelegramContentType::Start => {
-                        // Make a new one in temp
-                        temporary_stack.push(Vec::new());
-                        if let Some(last) = temporary_stack.last_mut() {
-                            last.push(content);
-                        }
-                    }
-                    TelegramContentType::End => {
-                        if let Some(mut last_telegram) = temporary_stack.pop() {
-                            last_telegram.push(content);
-                            completed_stack.push(build_telegram(last_telegram)?);
-                        }
-                        if !config
-                            .clone()
-                            .ok_or_else(|| return parse_error("Constructing configurator failed"))?
-                            .is_recursive
-                            && temporary_stack.len() > 0
-                        {
-                            return Err(parse_error("Recursive telegrams are not supported"));
-                        }
-                    }
-                    ref tct => {
-                        if !config
-                            .clone()
-                            .ok_or_else(|| return parse_error("Constructing configurator failed"))?
-                            .is_gas
-                            && matches!(tct, TelegramContentType::GasTotalDelivered)
-                        {
-                            return Err(parse_error("Gas data is not supported"));
-                        }
-                        if matches!(tct, TelegramContentType::InformationType) {
-                            if !config
-                                .clone()
-                                .ok_or_else(|| {
-                                    return parse_error("Constructing configurator failed");
-                                })?
-                                .is_gas
-                            {
-                                if let Some(Value::String(ref information_type)) = content.value {
-                                    if *information_type == "G".to_string() {
-                                        return Err(parse_error("Gas data is not supported"));
-                                    }
-                                }
-                            }
-                        }
-                        if let Some(last) = temporary_stack.last_mut() {
-                            last.push(content);
-                        }
-                    }
+    for content in contents {
+        process_content(content, config, state, completed_stack)?;
+    }
+
+    Ok(())
+}
+
+/// Moves a content onto the stack of the telegram it belongs to, see [`process_line`]
+fn process_content(
+    content: TelegramContent,
+    config: &ParserConfig,
+    state: &mut ParseState,
+    completed_stack: &mut Vec<Telegram>,
+) -> Result<(), MainError> {
+    let verify = config.checksum != ChecksumPolicy::Ignore;
+    let temporary_stack = &mut state.temporary_stack;
+
+    match content.telegram_content_type {
+        TelegramContentType::Start => {
+            if let Some(last) = temporary_stack.last_mut() {
+                last.contents.push(content);
+            }
+        }
+        TelegramContentType::End => {
+            if let Some(mut last_telegram) = temporary_stack.pop() {
+                last_telegram.contents.push(content);
+                let mut telegram = build_telegram(last_telegram.contents)?;
+                telegram.children = last_telegram.children;
+                match temporary_stack.last_mut() {
+                    Some(parent) => parent.children.push(telegram),
+                    None if verify => state.awaiting_footer = Some((telegram, last_telegram.crc)),
+                    None => completed_stack.push(telegram),
                 }
             }
-            Err(e) => {
-                eprintln!("error: failed to parse line {}: {:?}", index, e);
-                return Err(parse_error("Failed to parse line"));
+            if !config.is_recursive && !temporary_stack.is_empty() {
+                return Err(parse_error("Recursive telegrams are not supported"));
+            }
+        }
+        ref tct => {
+            if !config.is_gas && matches!(tct, TelegramContentType::GasTotalDelivered) {
+                return Err(parse_error("Gas data is not supported"));
+            }
+            if !config.is_gas && information_type_of(&content)? == Some(InformationType::Gas) {
+                return Err(parse_error("Gas data is not supported"));
+            }
+            if !config.is_utility && is_utility_content(&content)? {
+                return Err(parse_error("Water and heat data is not supported"));
+            }
+            if let Some(last) = temporary_stack.last_mut() {
+                match config.dialect {
+                    Dialect::Obis => obis::push_content(&mut last.contents, content)?,
+                    Dialect::Simplified => last.contents.push(content),
+                }
             }
         }
     }
 
-    completed_stack.reverse();
-    Ok(completed_stack)
+    Ok(())
+}
+
+/// Whether the content belongs to a water or heat telegram
+fn is_utility_content(content: &TelegramContent) -> Result<bool, MainError> {
+    Ok(match content.telegram_content_type {
+        TelegramContentType::WaterTotalDelivered | TelegramContentType::HeatTotalDelivered => true,
+        _ => matches!(
+            information_type_of(content)?,
+            Some(InformationType::Water | InformationType::Heat)
+        ),
+    })
+}
+
+/// The information type, if the content is the information type line
+fn information_type_of(content: &TelegramContent) -> Result<Option<InformationType>, MainError> {
+    match content.telegram_content_type {
+        TelegramContentType::InformationType => {
+            Ok(Some(InformationType::try_from(content.text()?)?))
+        }
+        _ => Ok(None),
+    }
 }
 
 pub fn parse_line(line: &str) -> Result<TelegramContent, MainError> {
+    parse_line_with(line, YearPivot::default())
+}
+
+/// Like [`parse_line`], expanding two digit years in dates with `year_pivot`
+pub fn parse_line_with(line: &str, year_pivot: YearPivot) -> Result<TelegramContent, MainError> {
     if !line.contains('(') || !line.contains(')') {
         return Err(parse_error("Invalid line format: missing parentheses"));
     }
@@ -185,8 +474,8 @@ pub fn parse_line(line: &str) -> Result<TelegramContent, MainError> {
     // ID
     let id = parse_id(id_part)?;
 
-    // Determine content type from ID
-    let content_type = determine_content_type(&id)?;
+    // Look up how a line with this ID is written
+    let spec = spec_for_id(&id).ok_or_else(|| parse_error(&format!("Unknown ID: {:?}", id)))?;
 
     // Parse value and unit
     let (value_str, unit) = if value_part.contains('*') {
@@ -194,150 +483,33 @@ pub fn parse_line(line: &str) -> Result<TelegramContent, MainError> {
         if value_parts.len() != 2 {
             return Err(parse_error("Invalid value-unit format"));
         }
-        (value_parts[0], Some(parse_unit(value_parts[1])?))
+        (value_parts[0], Some(parse_unit_of(spec, value_parts[1])?))
     } else {
         (value_part, None)
     };
 
     // Check value format
-    let value_len = value_str.to_string().len();
-    match content_type {
-        TelegramContentType::Start => {
-            if value_str != "START".to_string() {
-                return Err(parse_error("Invalid start block"));
-            }
-        }
-        TelegramContentType::EventlogSeverity => {
-            if value_str != "H".to_string() && value_str != "L".to_string() {
-                return Err(parse_error("Invalid eventlog severity"));
-            }
-        }
-        TelegramContentType::EventlogMessage => {
-            if value_len > 1024 {
-                return Err(parse_error("Invalid eventlog message"));
-            }
-        }
-        TelegramContentType::InformationType => {
-            if value_str != "E".to_string() && value_str != "G".to_string() {
-                return Err(parse_error("Invalid information type"));
-            }
-        }
-        TelegramContentType::End => {
-            if value_str != "END".to_string() {
-                return Err(parse_error("Invalid end block"));
-            }
-        }
-        TelegramContentType::Date | TelegramContentType::EventlogDate => {
-            if parse_date(value_str).is_err() {
-                return Err(parse_error("Invalid date block"));
-            }
-        }
-        TelegramContentType::Voltage => {
-            if !value_str.contains(".") {
-                return Err(parse_error("Invalid voltage value"));
-            }
-            let parts: Vec<&str> = value_str.split(".").collect();
-            if (parts[1].len() != 1 && parts[1].len() != 2) || value_len != 6 {
-                return Err(parse_error("Invalid voltage value"));
-            }
-        }
-        TelegramContentType::Current => {
-            if !value_str.contains(".") {
-                if value_len != 2 {
-                    return Err(parse_error("Invalid current value"));
-                }
-            } else {
-                let parts: Vec<&str> = value_str.split(".").collect();
-                if (parts[1].len() != 0 && parts[1].len() != 1) || value_len != 3 {
-                    return Err(parse_error("Invalid voltage value"));
-                }
-            }
-        }
-        TelegramContentType::Power => {
-            let ext_size = if !value_str.starts_with("+") && !value_str.starts_with("-") {
-                0
-            } else {
-                1
-            };
-            let parts: Vec<&str> = value_str.split(".").collect();
-            if parts[1].len() > 3 || value_len != 6 + ext_size {
-                return Err(parse_error("Invalid power value"));
-            }
-        }
-        TelegramContentType::TotalConsumed | TelegramContentType::TotalProduced => {
-            if !value_str.contains(".") {
-                if value_len != 10 {
-                    return Err(parse_error("Invalid cumulative power value"));
-                }
-            } else {
-                let parts: Vec<&str> = value_str.split(".").collect();
-                if parts[1].len() > 10 || value_len != 11 {
-                    return Err(parse_error("Invalid cumulative power value"));
-                }
-            }
-        }
-        TelegramContentType::GasTotalDelivered => {
-            if !value_str.contains(".") {
-                return Err(parse_error("Invalid gas value"));
-            }
-            let parts: Vec<&str> = value_str.split(".").collect();
-            if parts[1].len() != 3 || value_len != 9 {
-                return Err(parse_error("Invalid gas value"));
-            }
-        }
-    }
-
-    // Create appropriate TelegramContent based on type
-    let telegram_content = match content_type {
-        TelegramContentType::Start
-        | TelegramContentType::EventlogSeverity
-        | TelegramContentType::EventlogMessage
-        | TelegramContentType::InformationType
-        | TelegramContentType::End => Ok::<TelegramContent, MainError>(TelegramContent::new_value(
-            content_type,
-            id,
-            Value::String(value_str.to_string()),
-            unit,
-        )),
-
-        TelegramContentType::Date | TelegramContentType::EventlogDate => {
-            let date = parse_date(value_str)?;
-            Ok::<TelegramContent, MainError>(TelegramContent::new_value(
-                content_type,
-                id,
-                Value::Date(date),
-                unit,
-            ))
-        }
+    spec.check_format(value_str)?;
 
-        TelegramContentType::Voltage
-        | TelegramContentType::Current
-        | TelegramContentType::Power
-        | TelegramContentType::TotalConsumed
-        | TelegramContentType::TotalProduced
-        | TelegramContentType::GasTotalDelivered => {
-            let float_value = value_str
-                .parse::<f64>()
-                .map_err(|_| parse_error("Invalid float value"))?;
-            Ok::<TelegramContent, MainError>(TelegramContent::new_value(
-                content_type,
-                id,
-                Value::Float(float_value),
-                unit,
-            ))
-        }
-    }?;
+    // Create appropriate TelegramContent based on its kind of value
+    let value = match spec.kind {
+        ValueKind::Text => Value::String(value_str.to_string()),
+        ValueKind::Date => Value::Date(parse_date_with(value_str, year_pivot)?),
+        ValueKind::Float => Value::Number(
+            value_str
+                .parse()
+                .map_err(|_| parse_error("Invalid float value"))?,
+        ),
+    };
+    let telegram_content = TelegramContent::new_value(spec.content_type.clone(), id, value, unit);
 
-    if telegram_content.validate() {
-        Ok(telegram_content)
-    } else {
-        return Err(parse_error("Could not construct final telegram content"));
-    }
+    telegram_content.check()?;
+    Ok(telegram_content)
 }
 
 pub fn parse_id(id_str: &str) -> Result<(u32, u32, Option<u32>), MainError> {
     let digits: Vec<&str> = id_str.split('.').collect();
-    if digits.len() == 0 || digits.len() > 3 {
+    if digits.len() < 2 || digits.len() > 3 {
         return Err(parse_error("Invalid ID format"));
     }
 
@@ -363,22 +535,9 @@ pub fn parse_id(id_str: &str) -> Result<(u32, u32, Option<u32>), MainError> {
 pub fn determine_content_type(
     id: &(u32, u32, Option<u32>),
 ) -> Result<TelegramContentType, MainError> {
-    match id {
-        (1, 1, Some(0)) | (1, 1, _) => Ok(TelegramContentType::Start),
-        (1, 2, Some(0)) | (1, 2, _) => Ok(TelegramContentType::End),
-        (2, 1, None) => Ok(TelegramContentType::Date),
-        (3, 1, _) => Ok(TelegramContentType::EventlogSeverity),
-        (3, 2, _) => Ok(TelegramContentType::EventlogMessage),
-        (3, 3, _) => Ok(TelegramContentType::EventlogDate),
-        (4, 1, None) => Ok(TelegramContentType::InformationType),
-        (5, 2, None) => Ok(TelegramContentType::GasTotalDelivered),
-        (7, 1, _) => Ok(TelegramContentType::Voltage),
-        (7, 2, _) => Ok(TelegramContentType::Current),
-        (7, 3, _) => Ok(TelegramContentType::Power),
-        (7, 4, Some(1)) => Ok(TelegramContentType::TotalConsumed),
-        (7, 4, Some(2)) => Ok(TelegramContentType::TotalProduced),
-        _ => Err(parse_error(&format!("Unknown ID: {:?}", id))),
-    }
+    spec_for_id(id)
+        .map(|spec| spec.content_type.clone())
+        .ok_or_else(|| parse_error(&format!("Unknown ID: {:?}", id)))
 }
 
 pub fn parse_unit(unit_str: &str) -> Result<TelegramContentUnit, MainError> {
@@ -388,13 +547,30 @@ pub fn parse_unit(unit_str: &str) -> Result<TelegramContentUnit, MainError> {
         "KW" => Ok(TelegramContentUnit::KW),
         "KWH" => Ok(TelegramContentUnit::KWH),
         "M3" => Ok(TelegramContentUnit::M3),
+        "GJ" => Ok(TelegramContentUnit::GJ),
+        "S" => Ok(TelegramContentUnit::S),
         _ => Err(parse_error(&format!("Unknown unit: {}", unit_str))),
     }
 }
 
+/// Like [`parse_unit`], but also accepts the unit of a vendor specific line
+fn parse_unit_of(spec: &ContentSpec, unit_str: &str) -> Result<TelegramContentUnit, MainError> {
+    match &spec.unit {
+        Some(TelegramContentUnit::Custom(unit)) if unit.eq_ignore_ascii_case(unit_str) => {
+            Ok(TelegramContentUnit::Custom(unit.clone()))
+        }
+        _ => parse_unit(unit_str),
+    }
+}
+
 pub fn parse_date(date_str: &str) -> Result<Date, MainError> {
+    parse_date_with(date_str, YearPivot::default())
+}
+
+/// Like [`parse_date`], expanding a two digit year with `year_pivot`
+pub fn parse_date_with(date_str: &str, year_pivot: YearPivot) -> Result<Date, MainError> {
     // Expected format: "YY-MMM-dd hh:mm:ss (X)" where MMM is month name and X is S/W for DST
-    // Example: "23-Jul-05 15:26:41 (S)"
+    // Example: "23-Jul-05 15:26:41 (S)". The year may also be written with four digits.
 
     // Remove parentheses and split by space
     let cleaned = date_str.trim_start_matches('(').trim_end_matches(')');
@@ -410,9 +586,15 @@ pub fn parse_date(date_str: &str) -> Result<Date, MainError> {
         return Err(parse_error("Invalid date part format"));
     }
 
-    let year = format!("20{}", date_parts[0])
-        .parse::<u16>()
-        .map_err(|_| parse_error("Invalid year"))?;
+    let year_str = date_parts[0];
+    if !year_str.chars().all(|c| c.is_ascii_digit()) {
+        return Err(parse_error("Invalid year"));
+    }
+    let year = match year_str.len() {
+        2 => year_pivot.expand(year_str.parse().map_err(|_| parse_error("Invalid year"))?),
+        4 => year_str.parse().map_err(|_| parse_error("Invalid year"))?,
+        _ => return Err(parse_error("Invalid year")),
+    };
 
     let month = match date_parts[1] {
         "Jan" => 1,
@@ -458,12 +640,13 @@ pub fn parse_date(date_str: &str) -> Result<Date, MainError> {
         _ => return Err(parse_error("Invalid DST flag")),
     };
 
-    Ok(Date::new(year, month, day, hour, minute, seconds, dst))
+    Date::new(year, month, day, hour, minute, seconds, dst)
 }
 
 pub fn build_telegram(contents: Vec<TelegramContent>) -> Result<Telegram, MainError> {
     let mut start = None;
     let mut date = None;
+    let mut equipment_id = None;
     let mut eventlog_severity = Vec::new();
     let mut eventlog_message = Vec::new();
     let mut eventlog_date = Vec::new();
@@ -476,116 +659,389 @@ pub fn build_telegram(contents: Vec<TelegramContent>) -> Result<Telegram, MainEr
     let mut total_consumed = None;
     let mut total_produced = None;
     let mut total_gas_delivered = None;
+    let mut total_water_delivered = None;
+    let mut total_heat_delivered = None;
+    let mut tariff_consumed = Vec::new();
+    let mut tariff_produced = Vec::new();
+    let mut tariff_indicator = None;
+    let mut quality = Vec::new();
+    let mut extra = Vec::new();
 
     // Sort contents into appropriate fields
     for content in contents {
+        let index = |name: &str| {
+            content
+                .id
+                .2
+                .ok_or_else(|| parse_error(&format!("Could not unpack {name} ID")))
+        };
         match content.telegram_content_type {
-            TelegramContentType::Start => start = Some(content),
-            TelegramContentType::Date => date = Some(content),
-            TelegramContentType::InformationType => information_type = Some(content),
-            TelegramContentType::End => end = Some(content),
-            TelegramContentType::TotalConsumed => total_consumed = Some(content),
-            TelegramContentType::TotalProduced => total_produced = Some(content),
-            TelegramContentType::GasTotalDelivered => total_gas_delivered = Some(content),
+            TelegramContentType::Start => start = Some(Marker(content.id.2)),
+            TelegramContentType::Date => date = Some(content.date()?.clone()),
+            TelegramContentType::EquipmentId => {
+                equipment_id = Some(decode_message(content.text()?)?)
+            }
+            TelegramContentType::InformationType => {
+                information_type = Some(InformationType::try_from(content.text()?)?)
+            }
+            TelegramContentType::End => end = Some(Marker(content.id.2)),
+            TelegramContentType::TotalConsumed => total_consumed = Some(quantity(&content)?),
+            TelegramContentType::TotalProduced => total_produced = Some(quantity(&content)?),
+            TelegramContentType::GasTotalDelivered => {
+                total_gas_delivered = Some(quantity(&content)?)
+            }
+            TelegramContentType::WaterTotalDelivered => {
+                total_water_delivered = Some(quantity(&content)?)
+            }
+            TelegramContentType::HeatTotalDelivered => {
+                total_heat_delivered = Some(quantity(&content)?)
+            }
             TelegramContentType::EventlogSeverity => eventlog_severity.push((
-                content
-                    .id
-                    .2
-                    .ok_or_else(|| return parse_error("Could not unpack eventlog severity ID"))?,
-                content,
-            )),
-            TelegramContentType::EventlogMessage => eventlog_message.push((
-                content
-                    .id
-                    .2
-                    .ok_or_else(|| return parse_error("Could not unpack eventlog message ID"))?,
-                content,
-            )),
-            TelegramContentType::EventlogDate => eventlog_date.push((
-                content
-                    .id
-                    .2
-                    .ok_or_else(|| return parse_error("Could not unpack eventlog date ID"))?,
-                content,
+                index("eventlog severity")?,
+                Severity::try_from(content.text()?)?,
             )),
+            TelegramContentType::EventlogMessage => eventlog_message
+                .push((index("eventlog message")?, decode_message(content.text()?)?)),
+            TelegramContentType::EventlogDate => {
+                eventlog_date.push((index("eventlog date")?, content.date()?.clone()))
+            }
             TelegramContentType::Voltage => voltages.push(content),
             TelegramContentType::Current => currents.push(content),
             TelegramContentType::Power => powers.push(content),
+            TelegramContentType::TariffConsumed => tariff_consumed.push(content),
+            TelegramContentType::TariffProduced => tariff_produced.push(content),
+            TelegramContentType::TariffIndicator => {
+                tariff_indicator = Some(Tariff::try_from(content.text()?)?)
+            }
+            TelegramContentType::PowerFailures
+            | TelegramContentType::LongPowerFailures
+            | TelegramContentType::PowerFailureDate
+            | TelegramContentType::PowerFailureDuration
+            | TelegramContentType::VoltageSags
+            | TelegramContentType::VoltageSwells => quality.push(content),
+            TelegramContentType::Custom(_) => extra.push(content),
         }
     }
 
+    // A telegram holds the readings of a single meter
+    let has_electricity = !voltages.is_empty()
+        || !currents.is_empty()
+        || !powers.is_empty()
+        || total_consumed.is_some()
+        || total_produced.is_some()
+        || !tariff_consumed.is_empty()
+        || !tariff_produced.is_empty()
+        || tariff_indicator.is_some();
+    let meters = [
+        has_electricity,
+        total_gas_delivered.is_some(),
+        total_water_delivered.is_some(),
+        total_heat_delivered.is_some(),
+    ];
+    if meters.into_iter().filter(|&has| has).count() > 1 {
+        return Err(parse_error(
+            "Telegram has the readings of more than one kind of meter",
+        ));
+    }
+
     // Build TelegramBase
-    let base = TelegramBase::new(
+    let mut base = TelegramBase::new(
         start.ok_or_else(|| parse_error("Missing start field"))?,
         date.ok_or_else(|| parse_error("Missing date field"))?,
-        eventlog_severity,
-        eventlog_message,
-        eventlog_date,
+        eventlog_entries(eventlog_severity, eventlog_message, eventlog_date)?,
         information_type.ok_or_else(|| parse_error("Missing information_type field"))?,
         end.ok_or_else(|| parse_error("Missing end field"))?,
     );
+    base.equipment_id = equipment_id;
 
     // Determine data type and build TelegramData
-    let data =
-        if let Some(gas_delivered) = total_gas_delivered {
-            TelegramData::Gas {
-                total_gas_delivered: gas_delivered,
-            }
-        } else if voltages.len() >= 3
-            && currents.len() >= 3
-            && powers.len() >= 3
-            && total_consumed.is_some()
-            && total_produced.is_some()
+    let is_electricity = total_gas_delivered.is_none()
+        && total_water_delivered.is_none()
+        && total_heat_delivered.is_none();
+    if !quality.is_empty() && !is_electricity {
+        return Err(parse_error(
+            "Power failures and voltage quality belong in electricity telegrams",
+        ));
+    }
+
+    let data = if let Some(gas_delivered) = total_gas_delivered {
+        TelegramData::Gas {
+            total_gas_delivered: gas_delivered,
+        }
+    } else if let Some(water_delivered) = total_water_delivered {
+        TelegramData::Water {
+            total_water_delivered: water_delivered,
+        }
+    } else if let Some(heat_delivered) = total_heat_delivered {
+        TelegramData::Heat {
+            total_heat_delivered: heat_delivered,
+        }
+    } else if !voltages.is_empty()
+        && (total_consumed.is_some() || !tariff_consumed.is_empty())
+        && (total_produced.is_some() || !tariff_produced.is_empty())
+    {
+        let tariffs = build_tariffs(tariff_consumed, tariff_produced, tariff_indicator)?;
+        let total_consumed = total_of_tariffs(
+            total_consumed,
+            tariffs.as_ref().map(|t| t.consumed),
+            "consumed",
+        )?;
+        let total_produced = total_of_tariffs(
+            total_produced,
+            tariffs.as_ref().map(|t| t.produced),
+            "produced",
+        )?;
+        let voltages: Vec<Voltage> = quantities(phases(voltages, "voltage")?)?;
+        let currents: Vec<Current> = quantities(phases(currents, "current")?)?;
+        let powers: Vec<Power> = quantities(phases(powers, "power")?)?;
+        if voltages.len() != currents.len() || voltages.len() != powers.len() {
+            return Err(parse_error(&format!(
+                "Expected as many voltages, currents and powers, found {}, {} and {}",
+                voltages.len(),
+                currents.len(),
+                powers.len()
+            )));
+        }
+
+        TelegramData::Electricity {
+            total_consumed: total_consumed
+                .ok_or_else(|| parse_error("Could not unpack consumed power value"))?,
+            total_produced: total_produced
+                .ok_or_else(|| parse_error("Could not unpack produced power value"))?,
+            tariffs,
+            quality: build_quality(quality, voltages.len())?,
+            voltages,
+            currents,
+            powers,
+        }
+    } else {
+        return Err(parse_error("Missing required fields for telegram"));
+    };
+
+    if data.information_type() != base.information_type {
+        return Err(parse_error(&format!(
+            "Information type {} does not match the readings of the telegram, expected {}",
+            base.information_type.code(),
+            data.information_type().code()
+        )));
+    }
+
+    let mut telegram = Telegram::new(base, data);
+    telegram.extra = extra;
+    Ok(telegram)
+}
+
+/// Joins the severity, message and date of every eventlog entry by their index. Every entry
+/// needs exactly one of each.
+fn eventlog_entries(
+    severities: Vec<(u32, Severity)>,
+    messages: Vec<(u32, String)>,
+    dates: Vec<(u32, Date)>,
+) -> Result<Vec<EventLogEntry>, MainError> {
+    let mut severities = by_index(severities, "severity")?;
+    let mut messages = by_index(messages, "message")?;
+    let mut dates = by_index(dates, "date")?;
+
+    let indices: BTreeSet<u32> = severities
+        .keys()
+        .chain(messages.keys())
+        .chain(dates.keys())
+        .copied()
+        .collect();
+    indices
+        .into_iter()
+        .map(|index| {
+            let missing = |field| MainError::MissingEventlogEntry { id: index, field };
+            Ok(EventLogEntry {
+                index,
+                severity: severities.remove(&index).ok_or(missing("severity"))?,
+                message: messages.remove(&index).ok_or(missing("message"))?,
+                date: dates.remove(&index).ok_or(missing("date"))?,
+            })
+        })
+        .collect()
+}
+
+fn by_index<T>(lines: Vec<(u32, T)>, field: &'static str) -> Result<BTreeMap<u32, T>, MainError> {
+    let mut map = BTreeMap::new();
+    for (id, value) in lines {
+        if map.insert(id, value).is_some() {
+            return Err(MainError::DuplicateEventlogEntry { id, field });
+        }
+    }
+    Ok(map)
+}
+
+/// The typed value of a numeric line
+fn quantity<Q: Quantity>(content: &TelegramContent) -> Result<Q, MainError> {
+    Ok(Q::new(content.number()?))
+}
+
+fn quantities<Q: Quantity>(contents: Vec<TelegramContent>) -> Result<Vec<Q>, MainError> {
+    contents.iter().map(quantity).collect()
+}
+
+/// The value of a counter, which is written as a whole number
+fn count(content: &TelegramContent) -> Result<u32, MainError> {
+    content
+        .number()?
+        .to_integer()
+        .and_then(|value| u32::try_from(value).ok())
+        .ok_or_else(|| parse_error("Expected a whole number"))
+}
+
+/// Sorts the readings of one kind by phase, checking that every phase from 1 up to the
+/// number of readings occurs exactly once
+fn phases(
+    mut contents: Vec<TelegramContent>,
+    name: &str,
+) -> Result<Vec<TelegramContent>, MainError> {
+    if contents.is_empty() {
+        return Err(parse_error(&format!("Missing {name} values")));
+    }
+    contents.sort_by_key(|content| content.id.2);
+    for (phase, content) in (1..).zip(&contents) {
+        if content.id.2 != Some(phase) {
+            return Err(parse_error(&format!(
+                "Expected exactly one {name} value for phase {phase}"
+            )));
+        }
+    }
+    Ok(contents)
+}
+
+/// The power failure and voltage quality lines are optional. If any of them is present, both
+/// failure counts must be too, every logged failure needs a date and a duration, and sags and
+/// swells are either left out or reported for every phase.
+fn build_quality(
+    contents: Vec<TelegramContent>,
+    phase_count: usize,
+) -> Result<Option<Box<PowerQuality>>, MainError> {
+    if contents.is_empty() {
+        return Ok(None);
+    }
+
+    let mut power_failures = None;
+    let mut long_power_failures = None;
+    let mut dates = Vec::new();
+    let mut durations = Vec::new();
+    let mut voltage_sags = Vec::new();
+    let mut voltage_swells = Vec::new();
+    for content in contents {
+        match content.telegram_content_type {
+            TelegramContentType::PowerFailures => power_failures = Some(count(&content)?),
+            TelegramContentType::LongPowerFailures => long_power_failures = Some(count(&content)?),
+            TelegramContentType::PowerFailureDate => dates.push(content),
+            TelegramContentType::PowerFailureDuration => durations.push(content),
+            TelegramContentType::VoltageSags => voltage_sags.push(content),
+            _ => voltage_swells.push(content),
+        }
+    }
+
+    // Pair the dates and durations of the log by their index
+    dates.sort_by_key(|content| content.id.2);
+    let mut failure_log: Vec<PowerFailure> = Vec::new();
+    for end in dates {
+        let index = end
+            .id
+            .2
+            .ok_or_else(|| parse_error("Could not unpack power failure ID"))?;
+        if failure_log
+            .last()
+            .is_some_and(|failure| failure.index == index)
         {
-            let voltage_array: [TelegramContent; 3] =
-                [
-                    voltages.clone().into_iter().nth(0).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_1 voltage value");
-                    })?,
-                    voltages.clone().into_iter().nth(1).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_2 voltage value");
-                    })?,
-                    voltages.clone().into_iter().nth(2).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_3 voltage value");
-                    })?,
-                ];
-            let current_array: [TelegramContent; 3] =
-                [
-                    currents.clone().into_iter().nth(0).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_1 current value");
-                    })?,
-                    currents.clone().into_iter().nth(1).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_2 current value");
-                    })?,
-                    currents.clone().into_iter().nth(2).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_3 current value");
-                    })?,
-                ];
-            let power_array: [TelegramContent; 3] =
-                [
-                    powers.clone().into_iter().nth(0).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_1 power value");
-                    })?,
-                    powers.clone().into_iter().nth(1).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_2 power value");
-                    })?,
-                    powers.clone().into_iter().nth(2).ok_or_else(|| {
-                        return parse_error("Could not unpack phase_3 power value");
-                    })?,
-                ];
-
-            TelegramData::Electricity {
-                voltages: voltage_array,
-                currents: current_array,
-                powers: power_array,
-                total_consumed: total_consumed
-                    .ok_or_else(|| return parse_error("Could not unpack consumed power value"))?,
-                total_produced: total_produced
-                    .ok_or_else(|| return parse_error("Could not unpack produced power value"))?,
-            }
-        } else {
-            return Err(parse_error("Missing required fields for telegram"));
+            return Err(parse_error(&format!("Duplicate power failure {index}")));
+        }
+        let position = durations
+            .iter()
+            .position(|duration| duration.id.2 == Some(index))
+            .ok_or_else(|| parse_error(&format!("Missing duration of power failure {index}")))?;
+        let duration = durations.swap_remove(position);
+        failure_log.push(PowerFailure {
+            index,
+            end: end.date()?.clone(),
+            duration: count(&duration)?.into(),
+        });
+    }
+    if let Some(duration) = durations.first() {
+        return Err(parse_error(&format!(
+            "Duration without a date for power failure {:?}",
+            duration.id.2
+        )));
+    }
+
+    let per_phase = |contents: Vec<TelegramContent>, name: &str| {
+        if contents.is_empty() {
+            return Ok(Vec::new());
+        }
+        let contents = phases(contents, name)?;
+        if contents.len() != phase_count {
+            return Err(parse_error(&format!(
+                "Expected a {name} count for each of the {phase_count} phases"
+            )));
+        }
+        contents.iter().map(count).collect()
+    };
+
+    Ok(Some(Box::new(PowerQuality {
+        power_failures: power_failures.ok_or_else(|| parse_error("Missing power failure count"))?,
+        long_power_failures: long_power_failures
+            .ok_or_else(|| parse_error("Missing long power failure count"))?,
+        failure_log,
+        voltage_sags: per_phase(voltage_sags, "voltage sag")?,
+        voltage_swells: per_phase(voltage_swells, "voltage swell")?,
+    })))
+}
+
+/// Either all tariff lines are present (both tariffs and the indicator), or none are
+/// The total of a dual-tariff meter is the sum of its tariff counters. Real meters only send
+/// the tariff counters, so a missing total is derived from them.
+fn total_of_tariffs(
+    total: Option<Energy>,
+    tariffs: Option<[Energy; 2]>,
+    name: &str,
+) -> Result<Option<Energy>, MainError> {
+    let Some([Energy(tariff_1), Energy(tariff_2)]) = tariffs else {
+        return Ok(total);
+    };
+    let sum = tariff_1
+        .checked_add(tariff_2)
+        .ok_or_else(|| MainError::DecimalOverflow(format!("{tariff_1} + {tariff_2}")))?;
+    match total {
+        Some(Energy(total)) if total != sum => Err(parse_error(&format!(
+            "Total {name} energy {total} does not match the sum of its tariffs, {sum}"
+        ))),
+        Some(total) => Ok(Some(total)),
+        None => Ok(Some(Energy(sum))),
+    }
+}
+
+fn build_tariffs(
+    consumed: Vec<TelegramContent>,
+    produced: Vec<TelegramContent>,
+    indicator: Option<Tariff>,
+) -> Result<Option<Box<TariffCounters>>, MainError> {
+    if consumed.is_empty() && produced.is_empty() && indicator.is_none() {
+        return Ok(None);
+    }
+
+    let per_tariff = |contents: Vec<TelegramContent>| -> Result<[Energy; 2], MainError> {
+        let find = |tariff: u32| {
+            let content = contents
+                .iter()
+                .find(|content| content.id.2 == Some(tariff))
+                .ok_or_else(|| parse_error(&format!("Missing counter for tariff {tariff}")))?;
+            quantity(content)
         };
+        if contents.len() != 2 {
+            return Err(parse_error("Expected a counter for tariff 1 and 2"));
+        }
+        Ok([find(1)?, find(2)?])
+    };
 
-    Ok(Telegram::new(base, data))
+    Ok(Some(Box::new(TariffCounters {
+        consumed: per_tariff(consumed)?,
+        produced: per_tariff(produced)?,
+        active: indicator.ok_or_else(|| parse_error("Missing tariff indicator"))?,
+    })))
 }
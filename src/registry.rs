@@ -0,0 +1,436 @@
+//! # Content type registry
+//! Every kind of line is declared once, as a [`ContentSpec`]: the ids it uses, the unit it
+//! expects, the kind of value it holds and how that value is written. The parser, the checks
+//! of [`TelegramContent`] and the serializer all look up the spec of a line here.
+//!
+//! Downstream crates can [`register`] their own vendor specific lines. These are parsed into
+//! [`TelegramContentType::Custom`] contents, which end up in [`crate::telegram::Telegram::extra`].
+//!
+//! The registry is global: a registered spec applies to every parser and serializer in the
+//! process, including those of unrelated code and of other tests in the same test binary. It
+//! cannot be unregistered, and its memory is leaked so that lookups can hand out `'static`
+//! references. Register the specs of a program once, at startup.
+use std::ops::RangeInclusive;
+use std::sync::RwLock;
+
+use crate::error::{parse_error, MainError};
+use crate::telegram::*;
+
+/// The third part of an id, e.g. the `1` of `7.1.1`
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubId {
+    /// With or without a third part
+    Any,
+    /// Without a third part
+    Absent,
+    /// With any third part
+    Present,
+    /// With exactly this third part
+    Exactly(u32),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdPattern {
+    pub major: u32,
+    pub minor: u32,
+    pub sub: SubId,
+}
+
+impl IdPattern {
+    pub const fn new(major: u32, minor: u32, sub: SubId) -> Self {
+        Self { major, minor, sub }
+    }
+
+    pub fn matches(&self, id: &(u32, u32, Option<u32>)) -> bool {
+        self.major == id.0
+            && self.minor == id.1
+            && match (&self.sub, id.2) {
+                (SubId::Any, _) | (SubId::Absent, None) | (SubId::Present, Some(_)) => true,
+                (SubId::Exactly(expected), Some(sub)) => *expected == sub,
+                _ => false,
+            }
+    }
+
+    /// Whether there is an id that matches both patterns
+    pub fn overlaps(&self, other: &IdPattern) -> bool {
+        let subs_overlap = match (&self.sub, &other.sub) {
+            (SubId::Any, _) | (_, SubId::Any) => true,
+            (SubId::Absent, SubId::Absent) => true,
+            (SubId::Absent, _) | (_, SubId::Absent) => false,
+            (SubId::Exactly(a), SubId::Exactly(b)) => a == b,
+            _ => true,
+        };
+        self.major == other.major && self.minor == other.minor && subs_overlap
+    }
+}
+
+/// Which [`Value`] a content holds
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueKind {
+    Text,
+    Date,
+    Float,
+}
+
+/// How the value of a line must be written
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValueFormat {
+    /// No constraint besides being a valid value of its kind
+    Any,
+    /// One of these texts
+    OneOf(&'static [&'static str]),
+    /// A text of at most this many characters
+    MaxLength(usize),
+    /// Hex encoded bytes, at most this many characters
+    Hex(usize),
+    /// A zero padded number of `width` characters, including the decimal point but excluding
+    /// the sign, with a number of decimals in `decimals`
+    Number {
+        width: usize,
+        decimals: RangeInclusive<usize>,
+        /// The point may be left out when there are no decimals, making the number one shorter
+        point_optional: bool,
+        /// The number may be prefixed with `+` or `-`
+        signed: bool,
+    },
+}
+
+impl ValueFormat {
+    pub fn matches(&self, value: &str) -> bool {
+        match self {
+            ValueFormat::Any => true,
+            ValueFormat::OneOf(options) => options.contains(&value),
+            ValueFormat::MaxLength(length) => value.len() <= *length,
+            ValueFormat::Hex(length) => {
+                value.len() <= *length
+                    && value.len().is_multiple_of(2)
+                    && value.chars().all(|c| c.is_ascii_hexdigit())
+            }
+            ValueFormat::Number {
+                width,
+                decimals,
+                point_optional,
+                signed,
+            } => {
+                let value = match value.strip_prefix(['+', '-']) {
+                    Some(unsigned) if *signed => unsigned,
+                    _ => value,
+                };
+                match value.split_once('.') {
+                    Some((_, fraction)) => {
+                        value.len() == *width && decimals.contains(&fraction.len())
+                    }
+                    None => *point_optional && value.len() + 1 == *width && decimals.contains(&0),
+                }
+            }
+        }
+    }
+}
+
+/// Declaration of a kind of line
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContentSpec {
+    pub content_type: TelegramContentType,
+    /// Used in error messages, e.g. `voltage value`
+    pub name: &'static str,
+    pub id: IdPattern,
+    pub unit: Option<TelegramContentUnit>,
+    pub kind: ValueKind,
+    pub format: ValueFormat,
+}
+
+impl ContentSpec {
+    /// Checks that `value` is written the way this spec expects
+    pub fn check_format(&self, value: &str) -> Result<(), MainError> {
+        if self.format.matches(value) {
+            return Ok(());
+        }
+        // Kept apart, as an unknown severity is its own kind of error
+        if self.content_type == TelegramContentType::EventlogSeverity {
+            return Err(MainError::UnknownSeverity(value.to_string()));
+        }
+        Err(parse_error(&format!("Invalid {}", self.name)))
+    }
+}
+
+const fn spec(
+    content_type: TelegramContentType,
+    name: &'static str,
+    id: IdPattern,
+    unit: Option<TelegramContentUnit>,
+    kind: ValueKind,
+    format: ValueFormat,
+) -> ContentSpec {
+    ContentSpec {
+        content_type,
+        name,
+        id,
+        unit,
+        kind,
+        format,
+    }
+}
+
+const fn number(
+    width: usize,
+    decimals: RangeInclusive<usize>,
+    point_optional: bool,
+    signed: bool,
+) -> ValueFormat {
+    ValueFormat::Number {
+        width,
+        decimals,
+        point_optional,
+        signed,
+    }
+}
+
+/// The lines of the assignment
+pub static BUILTIN: [ContentSpec; 25] = [
+    spec(
+        TelegramContentType::Start,
+        "start block",
+        IdPattern::new(1, 1, SubId::Any),
+        None,
+        ValueKind::Text,
+        ValueFormat::OneOf(&["START"]),
+    ),
+    spec(
+        TelegramContentType::Date,
+        "date",
+        IdPattern::new(2, 1, SubId::Absent),
+        None,
+        ValueKind::Date,
+        ValueFormat::Any,
+    ),
+    spec(
+        TelegramContentType::EquipmentId,
+        "equipment identifier",
+        IdPattern::new(2, 2, SubId::Absent),
+        None,
+        ValueKind::Text,
+        ValueFormat::Hex(96),
+    ),
+    spec(
+        TelegramContentType::EventlogSeverity,
+        "eventlog severity",
+        IdPattern::new(3, 1, SubId::Present),
+        None,
+        ValueKind::Text,
+        ValueFormat::OneOf(&["H", "L"]),
+    ),
+    spec(
+        TelegramContentType::EventlogMessage,
+        "eventlog message",
+        IdPattern::new(3, 2, SubId::Present),
+        None,
+        ValueKind::Text,
+        ValueFormat::MaxLength(1024),
+    ),
+    spec(
+        TelegramContentType::EventlogDate,
+        "eventlog date",
+        IdPattern::new(3, 3, SubId::Present),
+        None,
+        ValueKind::Date,
+        ValueFormat::Any,
+    ),
+    spec(
+        TelegramContentType::InformationType,
+        "information type",
+        IdPattern::new(4, 1, SubId::Absent),
+        None,
+        ValueKind::Text,
+        ValueFormat::OneOf(&["E", "G", "W", "H"]),
+    ),
+    spec(
+        TelegramContentType::Voltage,
+        "voltage value",
+        IdPattern::new(7, 1, SubId::Present),
+        Some(TelegramContentUnit::V),
+        ValueKind::Float,
+        number(6, 1..=2, false, false),
+    ),
+    spec(
+        TelegramContentType::Current,
+        "current value",
+        IdPattern::new(7, 2, SubId::Present),
+        Some(TelegramContentUnit::A),
+        ValueKind::Float,
+        number(3, 0..=1, true, false),
+    ),
+    spec(
+        TelegramContentType::Power,
+        "power value",
+        IdPattern::new(7, 3, SubId::Present),
+        Some(TelegramContentUnit::KW),
+        ValueKind::Float,
+        number(6, 0..=3, false, true),
+    ),
+    spec(
+        TelegramContentType::TotalConsumed,
+        "cumulative power value",
+        IdPattern::new(7, 4, SubId::Exactly(1)),
+        Some(TelegramContentUnit::KWH),
+        ValueKind::Float,
+        number(11, 0..=10, true, false),
+    ),
+    spec(
+        TelegramContentType::TotalProduced,
+        "cumulative power value",
+        IdPattern::new(7, 4, SubId::Exactly(2)),
+        Some(TelegramContentUnit::KWH),
+        ValueKind::Float,
+        number(11, 0..=10, true, false),
+    ),
+    spec(
+        TelegramContentType::TariffConsumed,
+        "tariff power value",
+        IdPattern::new(7, 5, SubId::Present),
+        Some(TelegramContentUnit::KWH),
+        ValueKind::Float,
+        number(11, 0..=10, true, false),
+    ),
+    spec(
+        TelegramContentType::TariffProduced,
+        "tariff power value",
+        IdPattern::new(7, 6, SubId::Present),
+        Some(TelegramContentUnit::KWH),
+        ValueKind::Float,
+        number(11, 0..=10, true, false),
+    ),
+    spec(
+        TelegramContentType::TariffIndicator,
+        "tariff indicator",
+        IdPattern::new(7, 7, SubId::Absent),
+        None,
+        ValueKind::Text,
+        ValueFormat::OneOf(&["0001", "0002"]),
+    ),
+    spec(
+        TelegramContentType::PowerFailures,
+        "power failure count",
+        IdPattern::new(8, 1, SubId::Absent),
+        None,
+        ValueKind::Float,
+        number(6, 0..=0, true, false),
+    ),
+    spec(
+        TelegramContentType::LongPowerFailures,
+        "power failure count",
+        IdPattern::new(8, 2, SubId::Absent),
+        None,
+        ValueKind::Float,
+        number(6, 0..=0, true, false),
+    ),
+    spec(
+        TelegramContentType::PowerFailureDate,
+        "power failure date",
+        IdPattern::new(8, 3, SubId::Present),
+        None,
+        ValueKind::Date,
+        ValueFormat::Any,
+    ),
+    spec(
+        TelegramContentType::PowerFailureDuration,
+        "power failure duration",
+        IdPattern::new(8, 4, SubId::Present),
+        Some(TelegramContentUnit::S),
+        ValueKind::Float,
+        number(11, 0..=0, true, false),
+    ),
+    spec(
+        TelegramContentType::VoltageSags,
+        "voltage sag count",
+        IdPattern::new(8, 5, SubId::Present),
+        None,
+        ValueKind::Float,
+        number(6, 0..=0, true, false),
+    ),
+    spec(
+        TelegramContentType::VoltageSwells,
+        "voltage swell count",
+        IdPattern::new(8, 6, SubId::Present),
+        None,
+        ValueKind::Float,
+        number(6, 0..=0, true, false),
+    ),
+    spec(
+        TelegramContentType::GasTotalDelivered,
+        "gas value",
+        IdPattern::new(5, 2, SubId::Absent),
+        Some(TelegramContentUnit::M3),
+        ValueKind::Float,
+        number(9, 3..=3, false, false),
+    ),
+    spec(
+        TelegramContentType::WaterTotalDelivered,
+        "water value",
+        IdPattern::new(5, 3, SubId::Absent),
+        Some(TelegramContentUnit::M3),
+        ValueKind::Float,
+        number(9, 3..=3, false, false),
+    ),
+    spec(
+        TelegramContentType::HeatTotalDelivered,
+        "heat value",
+        IdPattern::new(5, 4, SubId::Absent),
+        Some(TelegramContentUnit::GJ),
+        ValueKind::Float,
+        number(9, 3..=3, false, false),
+    ),
+    spec(
+        TelegramContentType::End,
+        "end block",
+        IdPattern::new(1, 2, SubId::Any),
+        None,
+        ValueKind::Text,
+        ValueFormat::OneOf(&["END"]),
+    ),
+];
+
+/// Specs registered by downstream crates. They are leaked, so they live as long as the program
+/// does.
+static CUSTOM: RwLock<Vec<&'static ContentSpec>> = RwLock::new(Vec::new());
+
+/// Registers a vendor specific kind of line. Its content type must be
+/// [`TelegramContentType::Custom`], and its ids may not overlap with those of any other spec.
+///
+/// This changes the global registry for the rest of the process and leaks `spec`, see the
+/// [module documentation](self). Registering a spec again fails, so each one leaks only once.
+pub fn register(spec: ContentSpec) -> Result<(), MainError> {
+    if !matches!(spec.content_type, TelegramContentType::Custom(_)) {
+        return Err(parse_error("Only custom content types can be registered"));
+    }
+
+    let mut custom = CUSTOM.write().unwrap_or_else(|e| e.into_inner());
+    let taken = BUILTIN
+        .iter()
+        .chain(custom.iter().copied())
+        .find(|other| other.id.overlaps(&spec.id) || other.content_type == spec.content_type);
+    if let Some(other) = taken {
+        return Err(parse_error(&format!(
+            "Custom content {:?} overlaps with {:?}",
+            spec.content_type, other.content_type
+        )));
+    }
+
+    custom.push(Box::leak(Box::new(spec)));
+    Ok(())
+}
+
+fn find(predicate: impl Fn(&ContentSpec) -> bool) -> Option<&'static ContentSpec> {
+    BUILTIN.iter().find(|spec| predicate(spec)).or_else(|| {
+        let custom = CUSTOM.read().unwrap_or_else(|e| e.into_inner());
+        custom.iter().copied().find(|spec| predicate(spec))
+    })
+}
+
+/// The spec of the line with this id
+pub fn spec_for_id(id: &(u32, u32, Option<u32>)) -> Option<&'static ContentSpec> {
+    find(|spec| spec.id.matches(id))
+}
+
+pub fn spec_for_type(content_type: &TelegramContentType) -> Option<&'static ContentSpec> {
+    find(|spec| spec.content_type == *content_type)
+}
@@ -1,13 +1,11 @@
 use std::fs;
 
-use crate::bail;
-
 use tudelft_dsmr_output_generator::voltage_over_time::create_voltage_over_time_graph;
 use tudelft_dsmr_output_generator::Graphs;
 
 use crate::error::MainError;
 use crate::parser::parse;
-use crate::telegram::Value;
+use crate::telegram::{Telegram, TelegramData};
 
 use crate::helpers::*;
 
@@ -19,28 +17,50 @@ pub fn run(test: bool) -> Result<(), MainError> {
             .expect("Failed to read two_packets.dsmr file")
     };
 
-    let telegrams = parse(&input);
-    let mut telegrams = telegrams?;
+    let telegrams = parse(input).inspect_err(|e| {
+        if let MainError::Diagnostic(d) = e {
+            eprintln!("{}", d.render());
+        }
+    })?;
 
-    telegrams.sort_by_key(|t| match &t.base.date.value {
-        Some(Value::Date(date)) => date.timestamp,
-        _ => bail!("Invalid timestamp"),
-    });
-    let telegrams = telegrams; // We can by now assume that telegrams are always sorted by date
+    report(&sort_by_date(telegrams))
+}
 
-    let voltages = process_voltages(&telegrams);
-    let current_over_time = process_currents(&telegrams);
-    let gas_delta_over_time = process_gas_data(&telegrams);
-    let energy_pair_delta_over_time = process_energy_data(&telegrams);
+/// Sorts telegrams by their date, keeping the order of telegrams with the same date
+pub fn sort_by_date(mut telegrams: Vec<Telegram>) -> Vec<Telegram> {
+    telegrams.sort_by_key(Telegram::timestamp);
+    telegrams
+}
 
-    let mut result = Graphs::new()?;
-    process_event_logs(&telegrams, &mut result);
+/// Generates the graphs and eventlog of telegrams that are sorted by date
+pub fn report(telegrams: &[Telegram]) -> Result<(), MainError> {
+    let voltages = process_voltages(telegrams);
+    let current_over_time = process_currents(telegrams);
+    let gas_delta_over_time = process_gas_data(telegrams)?;
+    let energy_pair_delta_over_time = process_energy_data(telegrams)?;
+    // Only plotted when there are water telegrams, which needs the utilities extension
+    let has_water = telegrams
+        .iter()
+        .any(|t| matches!(t.data, TelegramData::Water { .. }));
+    let water_delta_over_time = process_water_data(telegrams)?;
 
-    result.add_graph(create_voltage_over_time_graph(voltages))?;
-    result.add_graph(current_over_time)?;
-    result.add_graph(gas_delta_over_time)?;
-    result.add_graph(energy_pair_delta_over_time)?;
-    let _ = result.generate();
+    let mut result = Graphs::new()?;
+    let added = (|| {
+        process_event_logs(telegrams, &mut result);
+        process_power_failures(telegrams, &mut result);
+        result.add_graph(create_voltage_over_time_graph(voltages))?;
+        result.add_graph(current_over_time)?;
+        result.add_graph(gas_delta_over_time)?;
+        result.add_graph(energy_pair_delta_over_time)?;
+        if has_water {
+            result.add_graph(water_delta_over_time)?;
+        }
+        Ok::<(), MainError>(())
+    })();
+    // Graphs panics when dropped without generating, so always generate
+    let generated = result.generate();
 
+    added?;
+    generated?;
     Ok(())
 }
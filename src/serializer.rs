@@ -0,0 +1,435 @@
+//! # Serializer
+//! Writes telegrams back into the text format that [`crate::parser`] reads.
+use crate::checksum::{crc16, crc16_update, serialize_footer};
+use crate::decimal::Decimal;
+use crate::error::{parse_error, MainError};
+use crate::helpers::encode_message;
+use crate::obis::Dialect;
+use crate::parser::ParserConfig;
+use crate::registry::{spec_for_type, ValueFormat};
+use crate::telegram::*;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// Serializes a header and telegrams, such that [`crate::parser::parse_tree`] returns
+/// the same telegrams again. Nested telegrams are written inside their parent.
+/// Telegrams that had a checksum footer get one again, computed over their new text.
+///
+/// This takes the output of `parse_tree`, see [`serialize_flat`] for that of
+/// [`crate::parser::parse`].
+pub fn serialize(config: &ParserConfig, telegrams: &[Telegram]) -> Result<String, MainError> {
+    serialize_in_order(config, telegrams.iter())
+}
+
+/// Like [`serialize`], but for the flat, reversed telegrams that [`crate::parser::parse`]
+/// returns: they are written in document order, so `parse` returns the same telegrams again.
+pub fn serialize_flat(config: &ParserConfig, telegrams: &[Telegram]) -> Result<String, MainError> {
+    serialize_in_order(config, telegrams.iter().rev())
+}
+
+fn serialize_in_order<'a>(
+    config: &ParserConfig,
+    telegrams: impl Iterator<Item = &'a Telegram>,
+) -> Result<String, MainError> {
+    let mut result = serialize_header(config);
+    result.push('\n');
+    for (index, telegram) in telegrams.enumerate() {
+        if index > 0 {
+            result.push('\n');
+        }
+        let text = serialize_telegram(telegram)?;
+        result.push_str(&text);
+        if telegram.checksum.is_some() {
+            let crc = crc16_update(crc16(text.as_bytes()), b"!");
+            result.push_str(&serialize_footer(crc));
+            result.push('\n');
+        }
+    }
+    Ok(result)
+}
+
+pub fn serialize_header(config: &ParserConfig) -> String {
+    let version = format!("/v{}{}\\", config.version.0, config.version.1);
+    let extensions: String = [
+        (config.is_gas, 'g'),
+        (config.is_recursive, 'r'),
+        (config.dialect == Dialect::Obis, 'o'),
+        (config.is_utility, 'u'),
+    ]
+    .iter()
+    .filter(|(enabled, _)| *enabled)
+    .map(|(_, extension)| extension)
+    .collect();
+    if extensions.is_empty() {
+        version
+    } else {
+        format!("{version}+{extensions}")
+    }
+}
+
+/// Serializes a single telegram (including the ones nested in it), one line per content
+pub fn serialize_telegram(telegram: &Telegram) -> Result<String, MainError> {
+    let mut lines = Vec::new();
+    for content in telegram_contents(telegram)? {
+        lines.push(serialize_content(&content)?);
+    }
+
+    // Nested telegrams go right before the END line
+    let end = lines.pop();
+    for child in &telegram.children {
+        lines.push(String::new());
+        lines.push(serialize_telegram(child)?.trim_end().to_string());
+        lines.push(String::new());
+    }
+    lines.extend(end);
+
+    Ok(lines.join("\n") + "\n")
+}
+
+/// The lines of a telegram (excluding the ones nested in it), in the order they are written
+pub fn telegram_contents(telegram: &Telegram) -> Result<Vec<TelegramContent>, MainError> {
+    let base = &telegram.base;
+    let mut contents = vec![
+        line(
+            TelegramContentType::Start,
+            (1, 1, base.start.0),
+            Value::String("START".to_string()),
+            None,
+        ),
+        line(
+            TelegramContentType::Date,
+            (2, 1, None),
+            Value::Date(base.date.clone()),
+            None,
+        ),
+    ];
+    if let Some(equipment_id) = &base.equipment_id {
+        contents.push(line(
+            TelegramContentType::EquipmentId,
+            (2, 2, None),
+            Value::String(encode_message(equipment_id)?),
+            None,
+        ));
+    }
+
+    // Every kind of eventlog line is written in its own block
+    for entry in &base.eventlog {
+        contents.push(line(
+            TelegramContentType::EventlogSeverity,
+            (3, 1, Some(entry.index)),
+            Value::String(entry.severity.code().to_string()),
+            None,
+        ));
+    }
+    for entry in &base.eventlog {
+        contents.push(line(
+            TelegramContentType::EventlogMessage,
+            (3, 2, Some(entry.index)),
+            Value::String(encode_message(&entry.message)?),
+            None,
+        ));
+    }
+    for entry in &base.eventlog {
+        contents.push(line(
+            TelegramContentType::EventlogDate,
+            (3, 3, Some(entry.index)),
+            Value::Date(entry.date.clone()),
+            None,
+        ));
+    }
+
+    contents.push(line(
+        TelegramContentType::InformationType,
+        (4, 1, None),
+        Value::String(base.information_type.code().to_string()),
+        None,
+    ));
+
+    match &telegram.data {
+        TelegramData::Electricity {
+            voltages,
+            currents,
+            powers,
+            total_consumed,
+            total_produced,
+            tariffs,
+            quality,
+        } => {
+            contents.extend(per_phase(TelegramContentType::Voltage, (7, 1), voltages)?);
+            contents.extend(per_phase(TelegramContentType::Current, (7, 2), currents)?);
+            contents.extend(per_phase(TelegramContentType::Power, (7, 3), powers)?);
+            contents.push(reading(
+                TelegramContentType::TotalConsumed,
+                (7, 4, Some(1)),
+                *total_consumed,
+            )?);
+            contents.push(reading(
+                TelegramContentType::TotalProduced,
+                (7, 4, Some(2)),
+                *total_produced,
+            )?);
+            if let Some(tariffs) = tariffs {
+                contents.extend(per_phase(
+                    TelegramContentType::TariffConsumed,
+                    (7, 5),
+                    &tariffs.consumed,
+                )?);
+                contents.extend(per_phase(
+                    TelegramContentType::TariffProduced,
+                    (7, 6),
+                    &tariffs.produced,
+                )?);
+                contents.push(line(
+                    TelegramContentType::TariffIndicator,
+                    (7, 7, None),
+                    Value::String(tariffs.active.code().to_string()),
+                    None,
+                ));
+            }
+            if let Some(quality) = quality {
+                contents.extend(quality_contents(quality));
+            }
+        }
+        TelegramData::Gas {
+            total_gas_delivered,
+        } => contents.push(reading(
+            TelegramContentType::GasTotalDelivered,
+            (5, 2, None),
+            *total_gas_delivered,
+        )?),
+        TelegramData::Water {
+            total_water_delivered,
+        } => contents.push(reading(
+            TelegramContentType::WaterTotalDelivered,
+            (5, 3, None),
+            *total_water_delivered,
+        )?),
+        TelegramData::Heat {
+            total_heat_delivered,
+        } => contents.push(reading(
+            TelegramContentType::HeatTotalDelivered,
+            (5, 4, None),
+            *total_heat_delivered,
+        )?),
+    }
+
+    contents.extend(telegram.extra.iter().cloned());
+
+    contents.push(line(
+        TelegramContentType::End,
+        (1, 2, base.end.0),
+        Value::String("END".to_string()),
+        None,
+    ));
+    Ok(contents)
+}
+
+fn quality_contents(quality: &PowerQuality) -> Vec<TelegramContent> {
+    let count = |content_type, id, count: u32| {
+        line(
+            content_type,
+            id,
+            Value::Number(Decimal::new(count.into(), 0)),
+            None,
+        )
+    };
+    let mut contents = vec![
+        count(
+            TelegramContentType::PowerFailures,
+            (8, 1, None),
+            quality.power_failures,
+        ),
+        count(
+            TelegramContentType::LongPowerFailures,
+            (8, 2, None),
+            quality.long_power_failures,
+        ),
+    ];
+    for failure in &quality.failure_log {
+        contents.push(line(
+            TelegramContentType::PowerFailureDate,
+            (8, 3, Some(failure.index)),
+            Value::Date(failure.end.clone()),
+            None,
+        ));
+        contents.push(line(
+            TelegramContentType::PowerFailureDuration,
+            (8, 4, Some(failure.index)),
+            Value::Number(Decimal::new(failure.duration as i64, 0)),
+            Some(TelegramContentUnit::S),
+        ));
+    }
+    for (phase, sags) in (1..).zip(&quality.voltage_sags) {
+        contents.push(count(
+            TelegramContentType::VoltageSags,
+            (8, 5, Some(phase)),
+            *sags,
+        ));
+    }
+    for (phase, swells) in (1..).zip(&quality.voltage_swells) {
+        contents.push(count(
+            TelegramContentType::VoltageSwells,
+            (8, 6, Some(phase)),
+            *swells,
+        ));
+    }
+    contents
+}
+
+fn line(
+    content_type: TelegramContentType,
+    id: (u32, u32, Option<u32>),
+    value: Value,
+    unit: Option<TelegramContentUnit>,
+) -> TelegramContent {
+    TelegramContent::new_value(content_type, id, value, unit)
+}
+
+fn reading<Q: Quantity>(
+    content_type: TelegramContentType,
+    id: (u32, u32, Option<u32>),
+    quantity: Q,
+) -> Result<TelegramContent, MainError> {
+    Ok(line(
+        content_type,
+        id,
+        Value::Number(quantity.decimal()?),
+        Some(Q::UNIT),
+    ))
+}
+
+/// One line per phase (or tariff), numbered from 1
+fn per_phase<Q: Quantity>(
+    content_type: TelegramContentType,
+    (major, minor): (u32, u32),
+    quantities: &[Q],
+) -> Result<Vec<TelegramContent>, MainError> {
+    (1..)
+        .zip(quantities)
+        .map(|(phase, quantity)| {
+            reading(content_type.clone(), (major, minor, Some(phase)), *quantity)
+        })
+        .collect()
+}
+
+/// Serializes a single line, e.g. `7.1.1#(0241.7*V)`
+pub fn serialize_content(content: &TelegramContent) -> Result<String, MainError> {
+    let id = match content.id {
+        (major, minor, Some(sub)) => format!("{major}.{minor}.{sub}"),
+        (major, minor, None) => format!("{major}.{minor}"),
+    };
+
+    let value = match (&content.telegram_content_type, &content.value) {
+        (_, Some(Value::String(value))) => value.clone(),
+        (_, Some(Value::Date(date))) => serialize_date(date)?,
+        (content_type, Some(Value::Number(value))) => serialize_number(content_type, *value)?,
+        (_, None) => return Err(parse_error(&format!("Missing value for {id}"))),
+    };
+
+    let unit = match &content.unit {
+        Some(TelegramContentUnit::V) => "*V",
+        Some(TelegramContentUnit::A) => "*A",
+        Some(TelegramContentUnit::KW) => "*kW",
+        Some(TelegramContentUnit::KWH) => "*kWh",
+        Some(TelegramContentUnit::M3) => "*m3",
+        Some(TelegramContentUnit::GJ) => "*GJ",
+        Some(TelegramContentUnit::S) => "*s",
+        Some(TelegramContentUnit::Custom(unit)) => &format!("*{unit}"),
+        None => "",
+    };
+
+    Ok(format!("{id}#({value}{unit})"))
+}
+
+/// Serializes a date as `YY-MMM-dd hh:mm:ss (X)`. Years outside 2000-2099 are written with
+/// four digits, so they are read back the same regardless of the year pivot.
+pub fn serialize_date(date: &Date) -> Result<String, MainError> {
+    let year = match date.year {
+        2000..=2099 => format!("{:02}", date.year % 100),
+        1000..=9999 => date.year.to_string(),
+        _ => {
+            return Err(MainError::InvalidDate(format!(
+                "year {} does not fit in four digits",
+                date.year
+            )))
+        }
+    };
+    let month = MONTHS
+        .get(usize::from(date.month).wrapping_sub(1))
+        .ok_or_else(|| MainError::InvalidDate(format!("month {}", date.month)))?;
+
+    Ok(format!(
+        "{year}-{month}-{:02} {:02}:{:02}:{:02} ({})",
+        date.day,
+        date.hour,
+        date.minute,
+        date.seconds,
+        if date.dst { "S" } else { "W" }
+    ))
+}
+
+/// Formats a reading with the fixed width and precision the parser expects for its type,
+/// as declared in [`crate::registry`]
+fn serialize_number(
+    content_type: &TelegramContentType,
+    value: Decimal,
+) -> Result<String, MainError> {
+    let formatted = match spec_for_type(content_type).map(|spec| &spec.format) {
+        Some(ValueFormat::Number {
+            width,
+            decimals,
+            point_optional,
+            signed,
+        }) => fixed_width(value, *width, decimals.clone(), *signed).map(|formatted| {
+            // Whole numbers are written without a point, where that is allowed
+            if *point_optional && *decimals == (0..=0) {
+                formatted.trim_end_matches('.').to_string()
+            } else {
+                formatted
+            }
+        }),
+        Some(ValueFormat::Any) => Some(value.to_string()),
+        _ => None,
+    };
+
+    formatted.ok_or_else(|| {
+        parse_error(&format!(
+            "Value {value} cannot be represented as {content_type:?}"
+        ))
+    })
+}
+
+/// Zero-pads `value` to `width` characters (always including a `.`). The value keeps the
+/// decimals it was read with if `decimals` allows, otherwise it gets the least amount of
+/// decimals in `decimals` that still represents it exactly.
+fn fixed_width(
+    value: Decimal,
+    width: usize,
+    decimals: std::ops::RangeInclusive<usize>,
+    signed: bool,
+) -> Option<String> {
+    if value.is_negative() && !signed {
+        return None;
+    }
+    let sign = if value.is_negative() { "-" } else { "" };
+
+    let scale = value.scale() as usize;
+    let precision = if decimals.contains(&scale) {
+        scale
+    } else {
+        decimals
+            .clone()
+            .find(|precision| value.rescale(*precision as u32).is_some())?
+    };
+    let formatted = value.checked_abs()?.rescale(precision as u32)?.to_string();
+    let formatted = if formatted.contains('.') {
+        formatted
+    } else {
+        formatted + "."
+    };
+    Some(formatted)
+        .filter(|formatted| formatted.len() <= width)
+        .map(|formatted| format!("{sign}{formatted:0>width$}"))
+}
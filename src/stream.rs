@@ -0,0 +1,207 @@
+//! # Streaming parser
+//! Parses telegrams incrementally, as bytes arrive from e.g. a serial P1 port.
+use std::collections::VecDeque;
+use std::io::BufRead;
+
+use crate::checksum::ChecksumPolicy;
+use crate::error::{diagnostic, parse_error, MainError};
+use crate::parser::{
+    parse_header, process_line, strip_line_ending, ParseOptions, ParseState, ParserConfig,
+};
+use crate::telegram::Telegram;
+
+/// Longest line we are willing to buffer: a maximum length eventlog message plus its id
+pub const MAX_LINE_LENGTH: usize = 1024 + 64;
+
+/// Push-based telegram parser.
+///
+/// Feed it arbitrary chunks of bytes with [`TelegramStream::push`], and take out every telegram
+/// as soon as its `END` line came in with [`TelegramStream::next_telegram`]. Only the current
+/// (incomplete) line and the telegrams that are still open are kept in memory.
+///
+/// Nested telegrams are flattened out of their parent (see [`Telegram::flatten`]), so they are
+/// yielded once the outermost telegram is complete, children before their parent.
+/// When checksums are verified, a telegram is only complete once the line after its `END` line
+/// (which may be its checksum footer) came in, or the stream is finished. Under
+/// [`ChecksumPolicy::Reject`] a telegram without a footer is an error.
+pub struct TelegramStream {
+    buffer: Vec<u8>,
+    line_number: usize,
+    options: ParseOptions,
+    config: Option<ParserConfig>,
+    state: ParseState,
+    /// Line number and text of the last line that completed an outermost telegram
+    last_end: (usize, String),
+    completed: VecDeque<Telegram>,
+}
+
+impl TelegramStream {
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            line_number: 0,
+            options: ParseOptions::default(),
+            config: None,
+            state: ParseState::default(),
+            last_end: (0, String::new()),
+            completed: VecDeque::new(),
+        }
+    }
+
+    /// A stream that handles checksum footers according to `policy`
+    pub fn with_checksum_policy(policy: ChecksumPolicy) -> Self {
+        let mut stream = Self::new();
+        stream.options.checksum = policy;
+        stream
+    }
+
+    /// Header of the input, once its first line has come in
+    pub fn config(&self) -> Option<&ParserConfig> {
+        self.config.as_ref()
+    }
+
+    /// Parses all complete lines in `chunk` (together with whatever was left over from the
+    /// previous chunk). The stream should not be used anymore after this returned an error.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), MainError> {
+        let mut rest = chunk;
+        while let Some(position) = rest.iter().position(|b| *b == b'\n') {
+            self.buffer.extend_from_slice(&rest[..=position]);
+            rest = &rest[position + 1..];
+
+            let line = std::mem::take(&mut self.buffer);
+            self.process(&line)?;
+        }
+
+        if self.buffer.len() + rest.len() > MAX_LINE_LENGTH {
+            return Err(diagnostic(
+                self.line_number + 1,
+                &String::from_utf8_lossy(&self.buffer),
+                parse_error("Line is too long"),
+            ));
+        }
+        self.buffer.extend_from_slice(rest);
+
+        Ok(())
+    }
+
+    /// Processes the last line, in case the input does not end in a newline.
+    /// Telegrams that are still open at this point are dropped, just like [`crate::parser::parse`] does.
+    pub fn finish(&mut self) -> Result<(), MainError> {
+        if !self.buffer.is_empty() {
+            let line = std::mem::take(&mut self.buffer);
+            self.process(&line)?;
+        }
+        let Some(config) = &self.config else {
+            return Err(parse_error(
+                "Input is empty, did you connect your keyboard?",
+            ));
+        };
+        let mut completed: Vec<Telegram> = Vec::new();
+        let (line_number, line) = &self.last_end;
+        self.state
+            .finish(config, &mut completed)
+            .map_err(|e| diagnostic(*line_number, line, e))?;
+        self.completed
+            .extend(completed.into_iter().flat_map(Telegram::flatten));
+        self.state.reset();
+
+        Ok(())
+    }
+
+    /// Takes out the oldest telegram that is complete
+    pub fn next_telegram(&mut self) -> Option<Telegram> {
+        self.completed.pop_front()
+    }
+
+    /// Processes a line, including its line ending (if it has one)
+    fn process(&mut self, raw: &[u8]) -> Result<(), MainError> {
+        self.line_number += 1;
+        let raw = String::from_utf8_lossy(raw);
+        let line = strip_line_ending(&raw);
+        if line.len() > MAX_LINE_LENGTH {
+            return Err(diagnostic(
+                self.line_number,
+                &String::from_utf8_lossy(&line.as_bytes()[..MAX_LINE_LENGTH]),
+                parse_error("Line is too long"),
+            ));
+        }
+
+        let config = match &self.config {
+            Some(config) => config,
+            None => {
+                let config =
+                    parse_header(line).map_err(|e| diagnostic(self.line_number, line, e))?;
+                self.config = Some(config.with_options(&self.options));
+                return Ok(());
+            }
+        };
+
+        let mut completed: Vec<Telegram> = Vec::new();
+        let open = self.state.open_telegrams();
+        process_line(line, &raw, config, &mut self.state, &mut completed)
+            .map_err(|e| diagnostic(self.line_number, line, e))?;
+        if self.state.open_telegrams() == 0 && open > 0 {
+            self.last_end = (self.line_number, line.to_string());
+        }
+        self.completed
+            .extend(completed.into_iter().flat_map(Telegram::flatten));
+
+        Ok(())
+    }
+}
+
+impl Default for TelegramStream {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Iterator over the telegrams in a reader, parsing them while reading
+pub struct TelegramReader<R: BufRead> {
+    reader: R,
+    stream: TelegramStream,
+    done: bool,
+}
+
+impl<R: BufRead> TelegramReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            stream: TelegramStream::new(),
+            done: false,
+        }
+    }
+
+    fn fill(&mut self) -> Result<(), MainError> {
+        let chunk = self.reader.fill_buf()?;
+        if chunk.is_empty() {
+            self.done = true;
+            return self.stream.finish();
+        }
+
+        let length = chunk.len();
+        let result = self.stream.push(chunk);
+        self.reader.consume(length);
+        result
+    }
+}
+
+impl<R: BufRead> Iterator for TelegramReader<R> {
+    type Item = Result<Telegram, MainError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(telegram) = self.stream.next_telegram() {
+                return Some(Ok(telegram));
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(e) = self.fill() {
+                // There is no sensible way to continue after a failure
+                self.done = true;
+                return Some(Err(e));
+            }
+        }
+    }
+}
@@ -1,12 +1,22 @@
-use crate::bail;
+use crate::checksum::Checksum;
+use crate::decimal::Decimal;
+use crate::error::{parse_error, MainError};
+use crate::registry::{spec_for_type, ContentSpec, ValueKind};
 use crate::traits::Validatable;
 
-use tudelft_dsmr_output_generator::{date_to_timestamp, UnixTimeStamp};
+use std::cmp::Ordering;
+use std::ops::Sub;
+
+use chrono::{DateTime, Datelike, NaiveDate, TimeDelta, Timelike, Utc, Weekday};
+use tudelft_dsmr_output_generator::UnixTimeStamp;
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TelegramContentType {
     Start,
     Date,
+    /// Hex encoded, like eventlog messages
+    EquipmentId,
     EventlogSeverity,
     EventlogMessage,
     EventlogDate,
@@ -18,23 +28,54 @@ pub enum TelegramContentType {
     Power,
     TotalConsumed,
     TotalProduced,
+    /// Cumulative energy per tariff, see [`TariffCounters`]
+    TariffConsumed,
+    TariffProduced,
+    TariffIndicator,
+    /// Power failures and voltage quality, see [`PowerQuality`]
+    PowerFailures,
+    LongPowerFailures,
+    PowerFailureDate,
+    PowerFailureDuration,
+    VoltageSags,
+    VoltageSwells,
 
     /// Gas
     GasTotalDelivered,
 
+    /// Water and heat, see [`crate::parser::ParserConfig::is_utility`]
+    WaterTotalDelivered,
+    HeatTotalDelivered,
+
+    /// A vendor specific line, see [`crate::registry::register`]
+    Custom(String),
+
     End,
 }
 
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TelegramContentUnit {
     V,
     A,
     KW,
     KWH,
     M3,
+    GJ,
+    /// Seconds
+    S,
+    /// A unit of a vendor specific line, as it is written after the `*`
+    Custom(String),
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// A local Dutch date, as written in a telegram. The daylight saving time flag must match the
+/// calendar, which tells apart the two occurrences of the hour that repeats when summer time ends.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(try_from = "DateFields", into = "DateFields")
+)]
 pub struct Date {
     pub timestamp: UnixTimeStamp,
 
@@ -56,10 +97,32 @@ impl Date {
         minute: u8,
         seconds: u8,
         dst: bool,
-    ) -> Self {
-        Date {
-            timestamp: date_to_timestamp(year, month, day, hour, minute, seconds, dst)
-                .unwrap_or_else(|| bail!("Missing required field")),
+    ) -> Result<Self, MainError> {
+        let invalid = || {
+            MainError::InvalidDate(format!(
+                "{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{seconds:02}"
+            ))
+        };
+        if day == 0 || day > days_in_month(year, month).ok_or_else(invalid)? {
+            return Err(invalid());
+        }
+        let local = NaiveDate::from_ymd_opt(year.into(), month.into(), day.into())
+            .and_then(|date| date.and_hms_opt(hour.into(), minute.into(), seconds.into()))
+            .ok_or_else(invalid)?;
+        let utc = local.and_utc() - TimeDelta::hours(if dst { 2 } else { 1 });
+
+        // Also rejects the hour that is skipped when summer time starts, as it is neither
+        if is_summer_time(&utc) != dst {
+            return Err(MainError::InvalidDate(format!(
+                "{year:04}-{month:02}-{day:02} {hour:02}:{minute:02}:{seconds:02} is not {} \
+                 time in Europe/Amsterdam",
+                if dst { "summer" } else { "winter" }
+            )));
+        }
+        let timestamp = utc.timestamp();
+
+        Ok(Date {
+            timestamp,
 
             year,
             month,
@@ -68,31 +131,188 @@ impl Date {
             minute,
             seconds,
             dst,
+        })
+    }
+
+    /// The local Dutch date of a unix timestamp, with the daylight saving time flag set
+    pub fn from_timestamp(timestamp: UnixTimeStamp) -> Result<Self, MainError> {
+        let utc = DateTime::<Utc>::from_timestamp(timestamp, 0)
+            .ok_or_else(|| MainError::InvalidDate(format!("timestamp {timestamp}")))?;
+        let dst = is_summer_time(&utc);
+        let local = utc + TimeDelta::hours(if dst { 2 } else { 1 });
+
+        Date::new(
+            local.year() as u16,
+            local.month() as u8,
+            local.day() as u8,
+            local.hour() as u8,
+            local.minute() as u8,
+            local.second() as u8,
+            dst,
+        )
+    }
+
+    /// The date `delta` later (or earlier, when negative), in local Dutch time
+    pub fn checked_add(&self, delta: TimeDelta) -> Result<Self, MainError> {
+        Date::from_timestamp(self.timestamp + delta.num_seconds())
+    }
+}
+
+/// Dates are ordered by the moment they refer to
+impl Ord for Date {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let key = |date: &Date| {
+            let Date {
+                timestamp,
+                year,
+                month,
+                day,
+                hour,
+                minute,
+                seconds,
+                dst,
+            } = *date;
+            (timestamp, year, month, day, hour, minute, seconds, dst)
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
+impl PartialOrd for Date {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// The time between two dates, e.g. `&later - &earlier`
+impl Sub for &Date {
+    type Output = TimeDelta;
+
+    fn sub(self, other: &Date) -> TimeDelta {
+        TimeDelta::seconds(self.timestamp - other.timestamp)
+    }
+}
+
+pub fn is_leap_year(year: u16) -> bool {
+    year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400))
+}
+
+/// The number of days in a month, `None` if the month does not exist
+pub fn days_in_month(year: u16, month: u8) -> Option<u8> {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => Some(31),
+        4 | 6 | 9 | 11 => Some(30),
+        2 if is_leap_year(year) => Some(29),
+        2 => Some(28),
+        _ => None,
+    }
+}
+
+/// European summer time: from 01:00 UTC on the last Sunday of March,
+/// until 01:00 UTC on the last Sunday of October
+fn is_summer_time(utc: &DateTime<Utc>) -> bool {
+    let switch = |month: u32| {
+        NaiveDate::from_weekday_of_month_opt(utc.year(), month, Weekday::Sun, 5)
+            .or_else(|| NaiveDate::from_weekday_of_month_opt(utc.year(), month, Weekday::Sun, 4))
+            .and_then(|day| day.and_hms_opt(1, 0, 0))
+            .map(|switch| switch.and_utc())
+    };
+
+    match (switch(3), switch(10)) {
+        (Some(start), Some(end)) => *utc >= start && *utc < end,
+        _ => false,
+    }
+}
+
+/// How two digit years are expanded: years below the pivot are in the 2000s, the others in
+/// the 1900s. The default pivot of 100 puts every two digit year in the 2000s.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct YearPivot(pub u8);
+
+impl Default for YearPivot {
+    fn default() -> Self {
+        YearPivot(100)
+    }
+}
+
+impl YearPivot {
+    pub fn expand(self, year: u8) -> u16 {
+        if year < self.0 {
+            2000 + year as u16
+        } else {
+            1900 + year as u16
         }
     }
 }
 
+/// How a [`Date`] is (de)serialized: without the timestamp, which is computed again on
+/// deserialization, so a date that does not exist in Europe/Amsterdam is rejected
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct DateFields {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    seconds: u8,
+    dst: bool,
+}
+
+#[cfg(feature = "serde")]
+impl From<Date> for DateFields {
+    fn from(date: Date) -> Self {
+        Self {
+            year: date.year,
+            month: date.month,
+            day: date.day,
+            hour: date.hour,
+            minute: date.minute,
+            seconds: date.seconds,
+            dst: date.dst,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<DateFields> for Date {
+    type Error = MainError;
+
+    fn try_from(date: DateFields) -> Result<Self, Self::Error> {
+        Date::new(
+            date.year,
+            date.month,
+            date.day,
+            date.hour,
+            date.minute,
+            date.seconds,
+            date.dst,
+        )
+    }
+}
+
 impl Validatable for Date {
     fn validate(&self) -> bool {
-        // Add proper date validation logic here
-        self.month >= 1
-            && self.month <= 12
-            && self.day >= 1
-            && self.day <= 31
+        days_in_month(self.year, self.month).is_some_and(|days| (1..=days).contains(&self.day))
             && self.hour < 24
             && self.minute < 60
             && self.seconds < 60
     }
 }
 
+/// The value of a single line, before it is turned into the typed fields of a [`Telegram`]
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     String(String),
     Date(Date),
-    Float(f64),
+    /// A number, exactly as written
+    Number(Decimal),
 }
 
+/// A single line of a telegram
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TelegramContent {
     pub telegram_content_type: TelegramContentType,
     pub id: (u32, u32, Option<u32>),
@@ -115,215 +335,396 @@ impl TelegramContent {
         }
     }
 
+    fn spec(&self) -> Result<&'static ContentSpec, MainError> {
+        spec_for_type(&self.telegram_content_type).ok_or_else(|| {
+            parse_error(&format!(
+                "Unknown content type {:?}",
+                self.telegram_content_type
+            ))
+        })
+    }
+
     // Note that I realize that these types are dependent on eachotehr (so given an ID, we determine the telegram content type *based* on that)
     // These tests are just for when I still f- up the constructions of said telegram content types
-    fn is_id_correct(&self) -> bool {
-        match self.telegram_content_type {
-            TelegramContentType::Start => match self.id {
-                (1, 1, _) => true, // NOTE: childs do not have the third omitted digit, but parents do
-                _ => false,
-            },
-            TelegramContentType::Date => match self.id {
-                (2, 1, None) => true,
-                _ => false,
-            },
-            TelegramContentType::EventlogSeverity => match self.id {
-                (3, 1, Some(_)) => true,
-                _ => false,
-            },
-            TelegramContentType::EventlogMessage => match self.id {
-                (3, 2, Some(_)) => true,
-                _ => false,
-            },
-            TelegramContentType::EventlogDate => match self.id {
-                (3, 3, Some(_)) => true,
-                _ => false,
-            },
-            TelegramContentType::InformationType => match self.id {
-                (4, 1, None) => true,
-                _ => false,
-            },
-            TelegramContentType::Voltage => match self.id {
-                (7, 1, Some(_)) => true,
-                _ => false,
-            },
-            TelegramContentType::Current => match self.id {
-                (7, 2, Some(_)) => true,
-                _ => false,
-            },
-            TelegramContentType::Power => match self.id {
-                (7, 3, Some(_)) => true,
-                _ => false,
-            },
-            TelegramContentType::TotalConsumed => match self.id {
-                (7, 4, Some(1)) => true,
-                _ => false,
-            },
-            TelegramContentType::TotalProduced => match self.id {
-                (7, 4, Some(2)) => true,
-                _ => false,
-            },
-            TelegramContentType::GasTotalDelivered => match self.id {
-                (5, 2, None) => true,
-                _ => false,
-            },
-            TelegramContentType::End => match self.id {
-                (1, 2, _) => true, // NOTE: childs do not have the third omitted digit, but parents do
-                _ => false,
-            },
+    fn is_id_correct(&self) -> Result<bool, MainError> {
+        Ok(self.spec()?.id.matches(&self.id))
+    }
+
+    fn is_unit_correct(&self) -> Result<bool, MainError> {
+        let expected = match &self.spec()?.unit {
+            Some(expected) => expected,
+            None => return Ok(self.unit.is_none()),
+        };
+
+        let unit = self
+            .unit
+            .as_ref()
+            .ok_or_else(|| MainError::MissingUnit(self.telegram_content_type.clone()))?;
+        Ok(unit == expected)
+    }
+
+    fn is_value_correct(&self) -> Result<bool, MainError> {
+        Ok(matches!(
+            (&self.spec()?.kind, &self.value),
+            (ValueKind::Text, Some(Value::String(_)))
+                | (ValueKind::Date, Some(Value::Date(_)))
+                | (ValueKind::Float, Some(Value::Number(_)))
+        ))
+    }
+
+    /// The value of a text line
+    pub fn text(&self) -> Result<&str, MainError> {
+        match &self.value {
+            Some(Value::String(value)) => Ok(value),
+            _ => Err(parse_error("Expected a text value")),
         }
     }
 
-    fn is_unit_correct(&self) -> bool {
-        match self.telegram_content_type {
-            TelegramContentType::Voltage => match self
-                .unit
-                .as_ref()
-                .unwrap_or_else(|| bail!("Missing required field"))
-            {
-                TelegramContentUnit::V => true,
-                _ => false,
-            },
-            TelegramContentType::Current => match self
-                .unit
-                .as_ref()
-                .unwrap_or_else(|| bail!("Missing required field"))
-            {
-                TelegramContentUnit::A => true,
-                _ => false,
-            },
-            TelegramContentType::Power => match self
-                .unit
-                .as_ref()
-                .unwrap_or_else(|| bail!("Missing required field"))
-            {
-                TelegramContentUnit::KW => true,
-                _ => false,
-            },
-            TelegramContentType::TotalConsumed => match self
-                .unit
-                .as_ref()
-                .unwrap_or_else(|| bail!("Missing required field"))
-            {
-                TelegramContentUnit::KWH => true,
-                _ => false,
-            },
-            TelegramContentType::TotalProduced => match self
-                .unit
-                .as_ref()
-                .unwrap_or_else(|| bail!("Missing required field"))
-            {
-                TelegramContentUnit::KWH => true,
-                _ => false,
-            },
-            TelegramContentType::GasTotalDelivered => match self
-                .unit
-                .as_ref()
-                .unwrap_or_else(|| bail!("Missing required field"))
-            {
-                TelegramContentUnit::M3 => true,
-                _ => false,
-            },
-            _ => match self.unit.as_ref() {
-                None => true,
-                _ => false,
-            },
+    /// The value of a numeric line
+    pub fn number(&self) -> Result<Decimal, MainError> {
+        match &self.value {
+            Some(Value::Number(value)) => Ok(*value),
+            _ => Err(parse_error("Expected a numeric value")),
         }
     }
 
-    fn is_value_correct(&self) -> bool {
-        match self.telegram_content_type {
-            TelegramContentType::Start
-            | TelegramContentType::EventlogSeverity
-            | TelegramContentType::EventlogMessage
-            | TelegramContentType::InformationType
-            | TelegramContentType::End => {
-                if let Some(Value::String(_)) = self.value {
-                    return true;
-                } else {
-                    return false;
-                }
-            }
-            TelegramContentType::Date | TelegramContentType::EventlogDate => {
-                if let Some(Value::Date(_)) = self.value {
-                    return true;
-                } else {
-                    return false;
-                }
-            }
-            TelegramContentType::Voltage
-            | TelegramContentType::Current
-            | TelegramContentType::Power
-            | TelegramContentType::TotalConsumed
-            | TelegramContentType::TotalProduced
-            | TelegramContentType::GasTotalDelivered => {
-                if let Some(Value::Float(_)) = self.value {
-                    return true;
-                } else {
-                    return false;
-                }
+    /// The value of a date line
+    pub fn date(&self) -> Result<&Date, MainError> {
+        match &self.value {
+            Some(Value::Date(date)) => Ok(date),
+            _ => Err(parse_error("Expected a date")),
+        }
+    }
+
+    /// Same checks as [`Validatable::validate`], but reports *why* the content is invalid
+    pub fn check(&self) -> Result<(), MainError> {
+        if !self.is_unit_correct()? {
+            return Err(parse_error("Unit does not match the content type"));
+        }
+        if !self.is_id_correct()? {
+            return Err(parse_error("ID does not match the content type"));
+        }
+        if !self.is_value_correct()? {
+            return Err(parse_error("Value does not match the content type"));
+        }
+        if let Some(Value::Date(date)) = &self.value {
+            if !date.validate() {
+                return Err(MainError::InvalidDate(format!(
+                    "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+                    date.year, date.month, date.day, date.hour, date.minute, date.seconds
+                )));
             }
         }
+
+        Ok(())
     }
 }
 
 impl Validatable for TelegramContent {
     fn validate(&self) -> bool {
-        let id_check = self.is_id_correct();
-        let unit_check = self.is_unit_correct();
-        let value_check = self.is_value_correct();
+        match self.check() {
+            Ok(()) => true,
+            Err(e) => {
+                eprintln!("[ERROR] {e}");
+                false
+            }
+        }
+    }
+}
 
-        // Additional validation for date values
-        let date_validation = if let Some(Value::Date(date)) = &self.value {
-            date.validate()
-        } else {
-            true
-        };
+/// Severity of an eventlog entry
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Severity {
+    /// `H`
+    High,
+    /// `L`
+    Low,
+}
 
-        if !id_check {
-            eprintln!("[ERROR] id_check failed.");
+impl Severity {
+    /// The value as written in a telegram
+    pub fn code(self) -> &'static str {
+        match self {
+            Severity::High => "H",
+            Severity::Low => "L",
         }
-        if !unit_check {
-            eprintln!("[ERROR] unit_check failed.");
+    }
+}
+
+impl TryFrom<&str> for Severity {
+    type Error = MainError;
+
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        match code {
+            "H" => Ok(Severity::High),
+            "L" => Ok(Severity::Low),
+            _ => Err(MainError::UnknownSeverity(code.to_string())),
+        }
+    }
+}
+
+/// What kind of meter sent a telegram
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum InformationType {
+    /// `E`
+    Electricity,
+    /// `G`
+    Gas,
+    /// `W`, with the utilities extension
+    Water,
+    /// `H`, with the utilities extension
+    Heat,
+}
+
+impl InformationType {
+    /// The value as written in a telegram
+    pub fn code(self) -> &'static str {
+        match self {
+            InformationType::Electricity => "E",
+            InformationType::Gas => "G",
+            InformationType::Water => "W",
+            InformationType::Heat => "H",
         }
-        if !value_check {
-            eprintln!("[ERROR] value_check failed.");
+    }
+}
+
+impl TryFrom<&str> for InformationType {
+    type Error = MainError;
+
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        match code {
+            "E" => Ok(InformationType::Electricity),
+            "G" => Ok(InformationType::Gas),
+            "W" => Ok(InformationType::Water),
+            "H" => Ok(InformationType::Heat),
+            _ => Err(parse_error(&format!("Unknown information type {code}"))),
         }
-        if !date_validation {
-            eprintln!("[ERROR] date_validation failed.");
+    }
+}
+
+/// The tariff of a dual-tariff meter
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tariff {
+    /// Tariff 1, `0001`
+    Low,
+    /// Tariff 2, `0002`
+    Normal,
+}
+
+impl Tariff {
+    /// 1 (low) or 2 (normal)
+    pub fn number(self) -> u8 {
+        match self {
+            Tariff::Low => 1,
+            Tariff::Normal => 2,
         }
+    }
 
-        return id_check && unit_check && value_check && date_validation;
+    /// The value of the tariff indicator line
+    pub fn code(self) -> &'static str {
+        match self {
+            Tariff::Low => "0001",
+            Tariff::Normal => "0002",
+        }
     }
 }
 
-#[derive(Debug)]
+impl TryFrom<&str> for Tariff {
+    type Error = MainError;
+
+    fn try_from(code: &str) -> Result<Self, Self::Error> {
+        match code {
+            "0001" => Ok(Tariff::Low),
+            "0002" => Ok(Tariff::Normal),
+            _ => Err(parse_error("Invalid tariff indicator")),
+        }
+    }
+}
+
+/// A `START` or `END` line, with the sub id it was written with, e.g. `Some(0)` for `1.1.0`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Marker(pub Option<u32>);
+
+/// A reading in a fixed unit
+pub trait Quantity: Copy {
+    const UNIT: TelegramContentUnit;
+
+    fn new(value: Decimal) -> Self;
+    /// The reading as it is written in a telegram
+    fn decimal(self) -> Result<Decimal, MainError>;
+    /// The reading as a float, for plotting
+    fn value(self) -> f64;
+}
+
+/// Volts
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Voltage(pub f64);
+
+/// Amperes
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Current(pub f64);
+
+/// Kilowatts, negative when the phase delivers power to the grid
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Power(pub f64);
+
+/// Kilowatt hours. A cumulative counter, so it is kept exact.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Energy(pub Decimal);
+
+/// Cubic meters, of gas or water. A cumulative counter, so it is kept exact.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Volume(pub Decimal);
+
+/// Gigajoules of heat. A cumulative counter, so it is kept exact.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HeatEnergy(pub Decimal);
+
+impl Quantity for Voltage {
+    const UNIT: TelegramContentUnit = TelegramContentUnit::V;
+
+    fn new(value: Decimal) -> Self {
+        Voltage(value.to_f64())
+    }
+
+    fn decimal(self) -> Result<Decimal, MainError> {
+        Decimal::from_f64(self.0)
+    }
+
+    fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl Quantity for Current {
+    const UNIT: TelegramContentUnit = TelegramContentUnit::A;
+
+    fn new(value: Decimal) -> Self {
+        Current(value.to_f64())
+    }
+
+    fn decimal(self) -> Result<Decimal, MainError> {
+        Decimal::from_f64(self.0)
+    }
+
+    fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl Quantity for Power {
+    const UNIT: TelegramContentUnit = TelegramContentUnit::KW;
+
+    fn new(value: Decimal) -> Self {
+        Power(value.to_f64())
+    }
+
+    fn decimal(self) -> Result<Decimal, MainError> {
+        Decimal::from_f64(self.0)
+    }
+
+    fn value(self) -> f64 {
+        self.0
+    }
+}
+
+impl Quantity for Energy {
+    const UNIT: TelegramContentUnit = TelegramContentUnit::KWH;
+
+    fn new(value: Decimal) -> Self {
+        Energy(value)
+    }
+
+    fn decimal(self) -> Result<Decimal, MainError> {
+        Ok(self.0)
+    }
+
+    fn value(self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+impl Quantity for Volume {
+    const UNIT: TelegramContentUnit = TelegramContentUnit::M3;
+
+    fn new(value: Decimal) -> Self {
+        Volume(value)
+    }
+
+    fn decimal(self) -> Result<Decimal, MainError> {
+        Ok(self.0)
+    }
+
+    fn value(self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+impl Quantity for HeatEnergy {
+    const UNIT: TelegramContentUnit = TelegramContentUnit::GJ;
+
+    fn new(value: Decimal) -> Self {
+        HeatEnergy(value)
+    }
+
+    fn decimal(self) -> Result<Decimal, MainError> {
+        Ok(self.0)
+    }
+
+    fn value(self) -> f64 {
+        self.0.to_f64()
+    }
+}
+
+/// One entry of the eventlog, made up of its `3.1.n` (severity), `3.2.n` (message) and
+/// `3.3.n` (date) lines
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EventLogEntry {
+    /// The `n` of the lines
+    pub index: u32,
+    pub severity: Severity,
+    /// Decoded, telegrams contain it hex encoded (see [`crate::helpers::decode_message`])
+    pub message: String,
+    pub date: Date,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TelegramBase {
-    pub start: TelegramContent,
-    pub date: TelegramContent,
-    pub eventlog_severities: Vec<(u32, TelegramContent)>,
-    pub eventlog_messages: Vec<(u32, TelegramContent)>,
-    pub eventlog_dates: Vec<(u32, TelegramContent)>,
-    pub information_type: TelegramContent,
-    pub end: TelegramContent,
+    pub start: Marker,
+    pub date: Date,
+    /// The decoded identifier of the meter that sent the telegram, if it identifies itself
+    pub equipment_id: Option<String>,
+    /// Sorted by index, every index occurs once
+    pub eventlog: Vec<EventLogEntry>,
+    pub information_type: InformationType,
+    pub end: Marker,
 }
 
 impl TelegramBase {
     pub fn new(
-        start: TelegramContent,
-        date: TelegramContent,
-        eventlog_severities: Vec<(u32, TelegramContent)>,
-        eventlog_messages: Vec<(u32, TelegramContent)>,
-        eventlog_dates: Vec<(u32, TelegramContent)>,
-        information_type: TelegramContent,
-        end: TelegramContent,
+        start: Marker,
+        date: Date,
+        eventlog: Vec<EventLogEntry>,
+        information_type: InformationType,
+        end: Marker,
     ) -> Self {
         Self {
             start,
             date,
-            eventlog_severities,
-            eventlog_messages,
-            eventlog_dates,
+            equipment_id: None,
+            eventlog,
             information_type,
             end,
         }
@@ -331,27 +732,164 @@ impl TelegramBase {
 }
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TelegramData {
+    /// The readings per phase are sorted by phase, and there are equally many of each
     Electricity {
-        voltages: [TelegramContent; 3],
-        currents: [TelegramContent; 3],
-        powers: [TelegramContent; 3],
-        total_consumed: TelegramContent,
-        total_produced: TelegramContent,
+        voltages: Vec<Voltage>,
+        currents: Vec<Current>,
+        powers: Vec<Power>,
+        total_consumed: Energy,
+        total_produced: Energy,
+        /// Only for meters that count low and normal tariff separately
+        tariffs: Option<Box<TariffCounters>>,
+        /// Only for meters that report power failures and voltage quality
+        quality: Option<Box<PowerQuality>>,
     },
     Gas {
-        total_gas_delivered: TelegramContent,
+        total_gas_delivered: Volume,
+    },
+    Water {
+        total_water_delivered: Volume,
+    },
+    Heat {
+        total_heat_delivered: HeatEnergy,
     },
 }
 
-#[derive(Debug)]
+impl TelegramData {
+    /// The information type (`4.1`) a telegram with this data has
+    pub fn information_type(&self) -> InformationType {
+        match self {
+            TelegramData::Electricity { .. } => InformationType::Electricity,
+            TelegramData::Gas { .. } => InformationType::Gas,
+            TelegramData::Water { .. } => InformationType::Water,
+            TelegramData::Heat { .. } => InformationType::Heat,
+        }
+    }
+}
+
+/// Energy counters of a dual-tariff meter. Index 0 holds tariff 1 (low), index 1 holds
+/// tariff 2 (normal).
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TariffCounters {
+    pub consumed: [Energy; 2],
+    pub produced: [Energy; 2],
+    /// The tariff that is active at the time of the telegram
+    pub active: Tariff,
+}
+
+/// Power failures and voltage quality, as counted by the meter
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerQuality {
+    /// Number of power failures in any phase
+    pub power_failures: u32,
+    /// Number of power failures in any phase that lasted long (DSMR: over 3 minutes)
+    pub long_power_failures: u32,
+    /// The most recent long power failures, sorted by index
+    pub failure_log: Vec<PowerFailure>,
+    /// Number of voltage sags per phase, empty if the meter does not report them
+    pub voltage_sags: Vec<u32>,
+    /// Number of voltage swells per phase, empty if the meter does not report them
+    pub voltage_swells: Vec<u32>,
+}
+
+/// An entry of the long power failure log
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PowerFailure {
+    pub index: u32,
+    /// When the power came back
+    pub end: Date,
+    /// How long the failure lasted, in seconds
+    pub duration: u64,
+}
+
+#[derive(Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Telegram {
     pub base: TelegramBase,
     pub data: TelegramData,
+    /// Telegrams nested inside this one (only with the recursive extension)
+    pub children: Vec<Telegram>,
+    /// The checksum footer of this telegram, if it had one and it was not ignored,
+    /// see [`crate::checksum`]
+    pub checksum: Option<Checksum>,
+    /// Vendor specific lines, see [`crate::registry`]
+    pub extra: Vec<TelegramContent>,
 }
 
 impl Telegram {
     pub fn new(base: TelegramBase, data: TelegramData) -> Self {
-        Self { base, data }
+        Self {
+            base,
+            data,
+            children: Vec::new(),
+            checksum: None,
+            extra: Vec::new(),
+        }
+    }
+
+    /// Depth-first iterator over this telegram and all telegrams nested inside it,
+    /// parents before their children
+    pub fn iter(&self) -> TelegramTreeIter<'_> {
+        TelegramTreeIter {
+            stack: vec![(0, self)],
+        }
+    }
+
+    /// Number of telegrams in this tree, including this one
+    pub fn count(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Moves all nested telegrams out of this tree, in the order their `END` lines appear:
+    /// children before their parent
+    pub fn flatten(mut self) -> Vec<Telegram> {
+        let children = std::mem::take(&mut self.children);
+        let mut flattened: Vec<Telegram> =
+            children.into_iter().flat_map(Telegram::flatten).collect();
+        flattened.push(self);
+        flattened
+    }
+
+    /// Unix timestamp of the date field of this telegram
+    pub fn timestamp(&self) -> UnixTimeStamp {
+        self.base.date.timestamp
+    }
+}
+
+/// Iterator returned by [`Telegram::iter`]
+pub struct TelegramTreeIter<'a> {
+    stack: Vec<(usize, &'a Telegram)>,
+}
+
+impl<'a> TelegramTreeIter<'a> {
+    /// Also yields the depth of every telegram, where the root has depth 0
+    pub fn with_depth(mut self) -> impl Iterator<Item = (usize, &'a Telegram)> {
+        std::iter::from_fn(move || self.next_with_depth())
+    }
+
+    fn next_with_depth(&mut self) -> Option<(usize, &'a Telegram)> {
+        let (depth, telegram) = self.stack.pop()?;
+        // Reversed, so the first child is visited first
+        self.stack.extend(
+            telegram
+                .children
+                .iter()
+                .rev()
+                .map(|child| (depth + 1, child)),
+        );
+        Some((depth, telegram))
+    }
+}
+
+impl<'a> Iterator for TelegramTreeIter<'a> {
+    type Item = &'a Telegram;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_depth().map(|(_, telegram)| telegram)
     }
 }
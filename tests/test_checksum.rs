@@ -0,0 +1,211 @@
+use std::fs;
+
+use dsmr_assignment::checksum::*;
+use dsmr_assignment::error::MainError;
+use dsmr_assignment::parser::{parse_tree, parse_tree_with, ParseOptions};
+use dsmr_assignment::serializer::serialize;
+use dsmr_assignment::stream::TelegramStream;
+
+/// Adds a checksum footer after every telegram of an input without nested telegrams
+fn with_footers(input: &str) -> String {
+    let (header, body) = input.split_once('\n').unwrap();
+    let telegrams: Vec<String> = body
+        .trim_end()
+        .split("\n\n")
+        .map(|telegram| {
+            let telegram = format!("{telegram}\n");
+            let crc = crc16_update(crc16(telegram.as_bytes()), b"!");
+            format!("{telegram}{}\n", serialize_footer(crc))
+        })
+        .collect();
+    format!("{header}\n{}", telegrams.join("\n"))
+}
+
+fn options(checksum: ChecksumPolicy) -> ParseOptions {
+    ParseOptions {
+        checksum,
+        ..ParseOptions::default()
+    }
+}
+
+#[test]
+fn test_crc16_check_value() {
+    // The standard check value of CRC-16/ARC
+    assert_eq!(crc16(b"123456789"), 0xBB3D);
+    assert_eq!(crc16_update(crc16(b"1234"), b"56789"), 0xBB3D);
+    assert_eq!(crc16(b""), 0);
+}
+
+#[test]
+fn test_parse_footer() {
+    assert_eq!(parse_footer("!BB3D").unwrap(), 0xBB3D);
+    assert_eq!(parse_footer("!bb3d").unwrap(), 0xBB3D);
+    assert_eq!(serialize_footer(0x0A1F), "!0A1F");
+    assert!(parse_footer("!BB3").is_err());
+    assert!(parse_footer("!BB3DD").is_err());
+    assert!(parse_footer("!XYZW").is_err());
+    assert!(parse_footer("BB3D").is_err());
+}
+
+#[test]
+fn test_policy_from_str() {
+    assert_eq!(
+        ChecksumPolicy::try_from("reject").unwrap(),
+        ChecksumPolicy::Reject
+    );
+    assert_eq!(
+        ChecksumPolicy::try_from("warn").unwrap(),
+        ChecksumPolicy::Warn
+    );
+    assert_eq!(
+        ChecksumPolicy::try_from("ignore").unwrap(),
+        ChecksumPolicy::Ignore
+    );
+    assert!(matches!(
+        ChecksumPolicy::try_from("strict"),
+        Err(MainError::UsageError(_))
+    ));
+}
+
+#[test]
+fn test_valid_footers() {
+    let input = with_footers(&fs::read_to_string("examples/good/two_packets.dsmr").unwrap());
+
+    let (telegrams, _) = parse_tree_with(&input, &options(ChecksumPolicy::Reject)).unwrap();
+    assert_eq!(telegrams.len(), 2);
+    for telegram in &telegrams {
+        assert!(telegram.checksum.unwrap().is_valid());
+    }
+}
+
+#[test]
+fn test_footers_with_crlf() {
+    let input = fs::read_to_string("examples/good/two_packets.dsmr")
+        .unwrap()
+        .replace('\n', "\r\n");
+    let (header, body) = input.split_once("\r\n").unwrap();
+    let telegram = body.split("\r\n\r\n").next().unwrap().to_string() + "\r\n";
+    let crc = crc16_update(crc16(telegram.as_bytes()), b"!");
+    let input = format!("{header}\r\n{telegram}{}\r\n", serialize_footer(crc));
+
+    let (telegrams, _) = parse_tree_with(&input, &options(ChecksumPolicy::Reject)).unwrap();
+    assert!(telegrams[0].checksum.unwrap().is_valid());
+}
+
+#[test]
+fn test_wrong_footer() {
+    let input = with_footers(&fs::read_to_string("examples/good/two_packets.dsmr").unwrap())
+        .replace("241.7", "241.8");
+
+    let e = parse_tree_with(&input, &options(ChecksumPolicy::Reject)).unwrap_err();
+    match e {
+        MainError::Diagnostic(d) => {
+            assert!(matches!(d.reason, MainError::ChecksumMismatch { .. }));
+            assert!(d.source.starts_with('!'));
+        }
+        other => panic!("Expected a diagnostic, got {other:?}"),
+    }
+
+    let (telegrams, _) = parse_tree_with(&input, &options(ChecksumPolicy::Warn)).unwrap();
+    assert_eq!(telegrams.len(), 2);
+    assert!(!telegrams[0].checksum.unwrap().is_valid());
+    assert!(telegrams[1].checksum.unwrap().is_valid());
+
+    let (telegrams, _) = parse_tree_with(&input, &options(ChecksumPolicy::Ignore)).unwrap();
+    assert_eq!(telegrams.len(), 2);
+    assert!(telegrams.iter().all(|t| t.checksum.is_none()));
+}
+
+#[test]
+fn test_lenient_drops_wrong_telegram() {
+    let input = with_footers(&fs::read_to_string("examples/good/two_packets.dsmr").unwrap())
+        .replace("241.7", "241.8");
+    let options = ParseOptions {
+        lenient: true,
+        checksum: ChecksumPolicy::Reject,
+        ..ParseOptions::default()
+    };
+
+    let (telegrams, diagnostics) = parse_tree_with(&input, &options).unwrap();
+    assert_eq!(telegrams.len(), 1);
+    assert_eq!(diagnostics.len(), 1);
+}
+
+#[test]
+fn test_misplaced_footer() {
+    let input = fs::read_to_string("examples/good/simple_gas.dsmr").unwrap();
+    // Right after the END line of the nested gas telegram
+    let nested_end = input.find("1.2.1#(END)").unwrap() + "1.2.1#(END)\n".len();
+    let input = format!("{}!0000\n{}", &input[..nested_end], &input[nested_end..]);
+
+    for policy in [
+        ChecksumPolicy::Reject,
+        ChecksumPolicy::Warn,
+        ChecksumPolicy::Ignore,
+    ] {
+        assert!(parse_tree_with(&input, &options(policy)).is_err());
+    }
+}
+
+#[test]
+fn test_stream_with_footers() {
+    let input = with_footers(&fs::read_to_string("examples/good/two_packets.dsmr").unwrap());
+
+    let mut stream = TelegramStream::with_checksum_policy(ChecksumPolicy::Reject);
+    let mut telegrams = Vec::new();
+    for chunk in input.as_bytes().chunks(5) {
+        stream.push(chunk).unwrap();
+        while let Some(telegram) = stream.next_telegram() {
+            telegrams.push(telegram);
+        }
+    }
+    stream.finish().unwrap();
+    telegrams.extend(std::iter::from_fn(|| stream.next_telegram()));
+
+    assert_eq!(telegrams.len(), 2);
+    assert!(telegrams.iter().all(|t| t.checksum.unwrap().is_valid()));
+}
+
+#[test]
+fn test_serialize_writes_footers() {
+    let input = with_footers(&fs::read_to_string("examples/good/two_packets.dsmr").unwrap());
+    let (telegrams, _) = parse_tree_with(&input, &options(ChecksumPolicy::Reject)).unwrap();
+    let config = dsmr_assignment::parser::parse_header(input.lines().next().unwrap()).unwrap();
+
+    let serialized = serialize(&config, &telegrams).unwrap();
+    assert_eq!(serialized.matches('!').count(), 2);
+    // The footers are computed over the serialized text, which formats numbers differently
+    let (reparsed, _) = parse_tree_with(&serialized, &options(ChecksumPolicy::Reject)).unwrap();
+    assert_eq!(reparsed.len(), telegrams.len());
+    for (reparsed, telegram) in reparsed.iter().zip(&telegrams) {
+        assert_eq!(reparsed.data, telegram.data);
+        assert!(reparsed.checksum.unwrap().is_valid());
+    }
+
+    // Without footers nothing changes
+    let plain = parse_tree(&fs::read_to_string("examples/good/two_packets.dsmr").unwrap()).unwrap();
+    assert!(!serialize(&config, &plain).unwrap().contains('!'));
+}
+
+#[test]
+fn test_missing_footer() {
+    let input = fs::read_to_string("examples/good/two_packets.dsmr").unwrap();
+
+    let e = parse_tree_with(&input, &options(ChecksumPolicy::Reject)).unwrap_err();
+    let MainError::Diagnostic(d) = e else {
+        panic!("expected a diagnostic, got {e}");
+    };
+    assert!(matches!(d.reason, MainError::ParseError(_)));
+    assert!(parse_tree_with(&input, &options(ChecksumPolicy::Warn)).is_ok());
+
+    // Only the footer of the last telegram is missing
+    let input = with_footers(&input);
+    let input = &input[..input.rfind('!').unwrap()];
+    assert!(parse_tree_with(input, &options(ChecksumPolicy::Reject)).is_err());
+
+    let mut stream = TelegramStream::with_checksum_policy(ChecksumPolicy::Reject);
+    let input = fs::read_to_string("examples/good/two_packets.dsmr").unwrap();
+    let pushed = stream.push(input.as_bytes()).and_then(|_| stream.finish());
+    assert!(pushed.is_err());
+    assert!(stream.next_telegram().is_none());
+}
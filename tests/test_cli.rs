@@ -0,0 +1,203 @@
+use std::fs;
+
+use dsmr_assignment::checksum::ChecksumPolicy;
+use dsmr_assignment::cli::{execute, parse_args, read_inputs, Command, ExportFormat};
+use dsmr_assignment::error::MainError;
+use dsmr_assignment::parser::{parse, ParseOptions};
+use dsmr_assignment::telegram::YearPivot;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|a| a.to_string()).collect()
+}
+
+#[test]
+fn test_parse_args_defaults_to_report() {
+    match parse_args(&args(&[])).unwrap() {
+        Command::Report { inputs, out, .. } => {
+            assert!(inputs.is_empty());
+            assert_eq!(out, None);
+        }
+        other => panic!("Expected report, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_args_subcommands() {
+    match parse_args(&args(&["parse", "--lenient", "a.dsmr", "-"])).unwrap() {
+        Command::Parse { inputs, options } => {
+            assert_eq!(inputs, ["a.dsmr", "-"]);
+            assert!(options.lenient);
+            assert_eq!(options.checksum, ChecksumPolicy::Ignore);
+        }
+        other => panic!("Expected parse, got {other:?}"),
+    }
+
+    match parse_args(&args(&["validate", "--year-pivot", "70"])).unwrap() {
+        Command::Validate { options, .. } => assert_eq!(options.year_pivot, YearPivot(70)),
+        other => panic!("Expected validate, got {other:?}"),
+    }
+
+    match parse_args(&args(&[
+        "export",
+        "--format",
+        "dsmr",
+        "--out",
+        "b.dsmr",
+        "--checksum",
+        "reject",
+        "a.dsmr",
+    ]))
+    .unwrap()
+    {
+        Command::Export {
+            inputs,
+            format,
+            out,
+            options,
+        } => {
+            assert_eq!(inputs, ["a.dsmr"]);
+            assert_eq!(options.checksum, ChecksumPolicy::Reject);
+            assert_eq!(format, ExportFormat::Dsmr);
+            assert_eq!(out.unwrap().to_str(), Some("b.dsmr"));
+        }
+        other => panic!("Expected export, got {other:?}"),
+    }
+
+    match parse_args(&args(&[
+        "generate", "--seed", "4", "--count", "2", "--nested",
+    ]))
+    .unwrap()
+    {
+        Command::Generate(config) => {
+            assert_eq!(config.seed, 4);
+            assert_eq!(config.count, 2);
+            assert!(config.is_recursive);
+        }
+        other => panic!("Expected generate, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_args_usage_errors() {
+    for bad in [
+        &["frobnicate"][..],
+        &["parse", "--out", "dir"],
+        &["report", "--out"],
+        &["export", "--format", "xml"],
+        &["validate", "--checksum", "maybe"],
+        &["parse", "--year-pivot", "101"],
+        &["generate", "--count", "many"],
+        &["generate", "input.dsmr"],
+    ] {
+        let e = parse_args(&args(bad)).unwrap_err();
+        assert!(matches!(e, MainError::UsageError(_)), "{bad:?}");
+        assert_eq!(e.exit_code(), 1);
+    }
+}
+
+#[test]
+fn test_exit_codes() {
+    let parse_failure = parse("not a telegram").unwrap_err();
+    assert_eq!(parse_failure.exit_code(), 2);
+
+    let io_failure = read_inputs(&args(&["examples/does_not_exist.dsmr"])).unwrap_err();
+    assert!(matches!(io_failure, MainError::IoError(_)));
+    assert_eq!(io_failure.exit_code(), 3);
+}
+
+#[test]
+fn test_read_inputs_expands_globs() {
+    let inputs = read_inputs(&args(&["examples/good_sequences/should_parse_[0-2].dsmr"])).unwrap();
+    let names: Vec<&str> = inputs.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "examples/good_sequences/should_parse_0.dsmr",
+            "examples/good_sequences/should_parse_1.dsmr",
+            "examples/good_sequences/should_parse_2.dsmr"
+        ]
+    );
+
+    let e = read_inputs(&args(&["examples/good/*.nothing"])).unwrap_err();
+    assert!(matches!(e, MainError::UsageError(_)));
+}
+
+#[test]
+fn test_validate_fails_on_bad_input() {
+    let good = Command::Validate {
+        inputs: args(&["examples/good/two_packets.dsmr"]),
+        options: ParseOptions::default(),
+    };
+    assert!(execute(good).is_ok());
+
+    let bad = Command::Validate {
+        inputs: args(&["examples/bad/corrupt_line.dsmr"]),
+        options: ParseOptions::default(),
+    };
+    assert_eq!(execute(bad).unwrap_err().exit_code(), 2);
+}
+
+#[test]
+fn test_export_dsmr_round_trips() {
+    let out = std::env::temp_dir().join("dsmr_test_export.dsmr");
+    execute(Command::Export {
+        inputs: args(&["examples/good/two_packets.dsmr"]),
+        format: ExportFormat::Dsmr,
+        out: Some(out.clone()),
+        options: ParseOptions::default(),
+    })
+    .unwrap();
+
+    let exported = fs::read_to_string(&out).unwrap();
+    let original = fs::read_to_string("examples/good/two_packets.dsmr").unwrap();
+    assert_eq!(parse(&exported).unwrap(), parse(&original).unwrap());
+}
+
+#[test]
+fn test_export_keeps_dialect_and_reports_bad_header() {
+    let out = std::env::temp_dir().join("dsmr_test_export_obis.dsmr");
+    execute(Command::Export {
+        inputs: args(&["examples/good/obis.dsmr"]),
+        format: ExportFormat::Dsmr,
+        out: Some(out.clone()),
+        options: ParseOptions::default(),
+    })
+    .unwrap();
+    let exported = fs::read_to_string(&out).unwrap();
+    assert!(exported.starts_with("/v12\\+gro\n"), "{exported}");
+
+    let bad_header = std::env::temp_dir().join("dsmr_test_export_bad_header.dsmr");
+    fs::write(&bad_header, "/v11\\\n1.1.0#(START)\n").unwrap();
+    let e = execute(Command::Export {
+        inputs: vec![bad_header.display().to_string()],
+        format: ExportFormat::Json,
+        out: None,
+        options: ParseOptions::default(),
+    })
+    .unwrap_err();
+    assert!(matches!(e, MainError::Diagnostic(_)), "{e:?}");
+}
+
+#[test]
+fn test_export_csv_writes_every_series() {
+    let out = std::env::temp_dir().join("dsmr_test_export_csv");
+    execute(Command::Export {
+        inputs: args(&["examples/good_sequences/should_parse_0.dsmr"]),
+        format: ExportFormat::Csv,
+        out: Some(out.clone()),
+        options: ParseOptions::default(),
+    })
+    .unwrap();
+
+    for file in ["voltages.csv", "currents.csv", "gas.csv", "energy.csv"] {
+        assert!(out.join(file).exists(), "{file}");
+    }
+
+    let no_out = Command::Export {
+        inputs: args(&["examples/good/two_packets.dsmr"]),
+        format: ExportFormat::Csv,
+        out: None,
+        options: ParseOptions::default(),
+    };
+    assert!(matches!(execute(no_out), Err(MainError::UsageError(_))));
+}
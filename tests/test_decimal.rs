@@ -0,0 +1,95 @@
+use dsmr_assignment::decimal::{Decimal, MAX_SCALE};
+
+#[test]
+fn test_parse_decimal() {
+    assert_eq!(
+        "12345.123".parse::<Decimal>().unwrap(),
+        Decimal::new(12345123, 3)
+    );
+    assert_eq!(
+        "0011454892.".parse::<Decimal>().unwrap(),
+        Decimal::new(11454892, 0)
+    );
+    assert_eq!("-005.01".parse::<Decimal>().unwrap(), Decimal::new(-501, 2));
+    assert_eq!("+.5".parse::<Decimal>().unwrap(), Decimal::new(5, 1));
+
+    // The declared precision is kept
+    assert_eq!("12345.120".parse::<Decimal>().unwrap().scale(), 3);
+
+    for invalid in [
+        "",
+        ".",
+        "-",
+        "1.2.3",
+        "02x1.7",
+        "1e5",
+        "1234567890123456789",
+    ] {
+        assert!(invalid.parse::<Decimal>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn test_display_decimal() {
+    assert_eq!(Decimal::new(12345120, 3).to_string(), "12345.120");
+    assert_eq!(Decimal::new(-5, 3).to_string(), "-0.005");
+    assert_eq!(Decimal::new(42, 0).to_string(), "42");
+}
+
+#[test]
+fn test_decimal_arithmetic_is_exact() {
+    let a: Decimal = "0.1".parse().unwrap();
+    let b: Decimal = "0.2".parse().unwrap();
+    assert_eq!(a.checked_add(b), Some("0.3".parse().unwrap()));
+    assert_ne!(0.1 + 0.2, 0.3);
+
+    let before: Decimal = "12345.123".parse().unwrap();
+    let after: Decimal = "12345.223".parse().unwrap();
+    assert_eq!(after.checked_sub(before).unwrap().to_f64(), 0.1);
+    assert_eq!(before.checked_sub(after), Some(Decimal::new(-1, 1)));
+}
+
+#[test]
+fn test_decimal_overflow() {
+    assert_eq!(
+        Decimal::new(i64::MAX, 0).checked_add(Decimal::new(1, 0)),
+        None
+    );
+    assert_eq!(Decimal::new(i64::MIN, 0).checked_neg(), None);
+    assert_eq!(Decimal::new(i64::MIN, 0).checked_abs(), None);
+    assert_eq!(Decimal::ZERO.checked_sub(Decimal::new(i64::MIN, 0)), None);
+
+    // Both fit, but not at a shared scale of 10 decimals
+    let small: Decimal = ".0000000001".parse().unwrap();
+    let large: Decimal = "9999999999.".parse().unwrap();
+    assert_eq!(large.checked_sub(small), None);
+    assert!(small < large);
+
+    // Comparing the largest scales does not overflow either
+    assert!(Decimal::new(i64::MIN, MAX_SCALE) < Decimal::new(i64::MAX, 0));
+    assert_eq!(Decimal::new(1, 0).rescale(MAX_SCALE + 1), None);
+    assert!(std::panic::catch_unwind(|| Decimal::new(1, MAX_SCALE + 1)).is_err());
+}
+
+#[test]
+fn test_decimal_scale() {
+    // The scale does not affect comparisons
+    assert_eq!(Decimal::new(15, 1), Decimal::new(150, 2));
+    assert!(Decimal::new(15, 1) < Decimal::new(151, 2));
+    assert!(Decimal::new(-1, 0) < Decimal::ZERO);
+
+    assert_eq!(Decimal::new(15, 1).rescale(3), Some(Decimal::new(1500, 3)));
+    assert_eq!(Decimal::new(1500, 3).rescale(1).unwrap().mantissa(), 15);
+    assert_eq!(Decimal::new(1501, 3).rescale(1), None);
+    assert_eq!(Decimal::new(1500, 3).normalize().scale(), 1);
+
+    assert_eq!(Decimal::new(300, 2).to_integer(), Some(3));
+    assert_eq!(Decimal::new(301, 2).to_integer(), None);
+}
+
+#[test]
+fn test_decimal_f64() {
+    assert_eq!(Decimal::from_f64(241.7).unwrap(), Decimal::new(2417, 1));
+    assert_eq!(Decimal::new(2417, 1).to_f64(), 241.7);
+    assert!(Decimal::from_f64(f64::NAN).is_err());
+}
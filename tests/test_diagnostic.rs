@@ -0,0 +1,78 @@
+use dsmr_assignment::error::MainError;
+use dsmr_assignment::parser::*;
+
+const BAD_VOLTAGE: &str = "/v10\\
+1.1.0#(START)
+2.1#(23-Jul-05 15:26:41 (S))
+4.1#(E)
+7.1.1#(02x1.7*V)
+1.2.0#(END)
+";
+
+fn diagnostic_of(input: &str) -> Box<dsmr_assignment::diagnostic::ParseDiagnostic> {
+    match parse(input) {
+        Err(MainError::Diagnostic(d)) => d,
+        other => panic!("Expected a diagnostic, got {:?}", other.err()),
+    }
+}
+
+#[test]
+fn test_diagnostic_points_at_value() {
+    let d = diagnostic_of(BAD_VOLTAGE);
+    assert_eq!(d.line, 5);
+    assert_eq!(d.span, 7..15);
+    assert_eq!(d.column(), 8);
+    assert_eq!(d.id.as_deref(), Some("7.1.1"));
+    assert_eq!(d.value.as_deref(), Some("02x1.7*V"));
+    assert!(matches!(d.reason, MainError::ParseError(_)));
+}
+
+#[test]
+fn test_diagnostic_points_at_unknown_id() {
+    let d = diagnostic_of("/v10\\\n1.1.0#(START)\n9.9.9#(1)\n");
+    assert_eq!(d.line, 3);
+    assert_eq!(d.span, 0..5);
+    assert_eq!(d.id.as_deref(), Some("9.9.9"));
+}
+
+#[test]
+fn test_diagnostic_on_obis_line() {
+    let input = "/v12\\+gro\n1.1.0#(START)\n1-0:32.7.0(230.12*V)\n";
+    let d = diagnostic_of(input);
+    assert_eq!(d.line, 3);
+    assert_eq!(d.span, 11..19);
+    assert_eq!(d.id.as_deref(), Some("1-0:32.7.0"));
+    assert_eq!(d.value.as_deref(), Some("230.12*V"));
+
+    // Of the gas reading, the reading itself is blamed rather than the time it was taken
+    let d =
+        diagnostic_of(&input.replace("1-0:32.7.0(230.12*V)", "0-1:24.2.1(230705150000S)(1.2*m3)"));
+    assert_eq!(d.value.as_deref(), Some("1.2*m3"));
+    assert_eq!(d.column(), 27);
+
+    let d = diagnostic_of(&input.replace("1-0:32.7.0", "1-0:99.7.0"));
+    assert_eq!(d.span, 0..10);
+}
+
+#[test]
+fn test_diagnostic_keeps_underlying_reason() {
+    let d = diagnostic_of("/v10\\\n1.1.0#(START)\n3.1.1#(X)\n");
+    assert!(matches!(d.reason, MainError::UnknownSeverity(_)));
+}
+
+#[test]
+fn test_diagnostic_for_header() {
+    let d = diagnostic_of("/v11\\\n");
+    assert_eq!(d.line, 1);
+    assert_eq!(d.span, 0..5);
+}
+
+#[test]
+fn test_diagnostic_render() {
+    let rendered = diagnostic_of(BAD_VOLTAGE).render();
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines[0], "error: Parse Error Occured: Invalid float value");
+    assert_eq!(lines[1], " --> line 5, column 8");
+    assert_eq!(lines[3], "5 | 7.1.1#(02x1.7*V)");
+    assert_eq!(lines[4], "  |        ^^^^^^^^");
+}
@@ -1,6 +1,12 @@
+#![allow(clippy::assertions_on_constants)]
+
 use tudelft_dsmr_output_generator::PlotError;
 
+use dsmr_assignment::decimal::Decimal;
 use dsmr_assignment::error::MainError;
+use dsmr_assignment::helpers::decode_message;
+use dsmr_assignment::parser::parse_line;
+use dsmr_assignment::telegram::*;
 
 #[test]
 fn test_main_error_from_io_error() {
@@ -24,3 +30,44 @@ fn test_main_error_from_plot_error() {
         _ => panic!("Expected PlotError variant"),
     }
 }
+
+#[test]
+fn test_invalid_date_does_not_exit() {
+    match Date::new(2023, 2, 31, 0, 0, 0, false) {
+        Err(MainError::InvalidDate(_)) => assert!(true),
+        _ => panic!("Expected InvalidDate variant"),
+    }
+}
+
+#[test]
+fn test_missing_unit_error() {
+    let content = TelegramContent {
+        telegram_content_type: TelegramContentType::Voltage,
+        id: (7, 1, Some(1)),
+        value: Some(Value::Number(Decimal::new(2300, 1))),
+        unit: None,
+    };
+
+    match content.check() {
+        Err(MainError::MissingUnit(TelegramContentType::Voltage)) => assert!(true),
+        _ => panic!("Expected MissingUnit variant"),
+    }
+}
+
+#[test]
+fn test_unknown_severity_error() {
+    match parse_line("3.1.1#(X)") {
+        Err(MainError::UnknownSeverity(severity)) => assert_eq!(severity, "X"),
+        _ => panic!("Expected UnknownSeverity variant"),
+    }
+}
+
+#[test]
+fn test_bad_hex_message_error() {
+    for message in ["4g", "486", "zz"] {
+        match decode_message(message) {
+            Err(MainError::BadHexMessage(_)) => assert!(true),
+            _ => panic!("Expected BadHexMessage variant for {message}"),
+        }
+    }
+}
@@ -0,0 +1,219 @@
+use std::fs;
+
+use itertools::Itertools;
+
+use tudelft_dsmr_output_generator::gas_over_time::GasData;
+
+use dsmr_assignment::export::*;
+use dsmr_assignment::helpers::{
+    current_phases, energy_series, gas_series, tariff_series, voltage_phases, PhaseData,
+};
+use dsmr_assignment::parser::{parse, parse_header, parse_tree};
+use dsmr_assignment::runner::sort_by_date;
+
+#[test]
+fn test_json_electricity() {
+    let input = fs::read_to_string("examples/good/two_packets.dsmr").unwrap();
+    let config = parse_header(input.lines().next().unwrap()).unwrap();
+    let json = json_value(&config, &parse_tree(&input).unwrap()).unwrap();
+
+    assert_eq!(json["schema"], JSON_SCHEMA_VERSION);
+    assert_eq!(json["version"], "1.0");
+    assert_eq!(json["gas"], false);
+    assert_eq!(json["recursive"], false);
+
+    let telegrams = json["telegrams"].as_array().unwrap();
+    assert_eq!(telegrams.len(), 2);
+
+    let first = &telegrams[0];
+    assert_eq!(first["information_type"], "E");
+    assert!(first["gas"].is_null());
+    assert_eq!(first["electricity"]["voltage"][0], 241.7);
+    assert_eq!(first["electricity"]["power"][1], -5.01);
+    assert_eq!(first["events"][0]["index"], 1);
+    assert_eq!(first["events"][0]["severity"], "H");
+    assert_eq!(first["events"][0]["message"], "Power Failure");
+    assert_eq!(first["events"][0]["date"], "2023-07-02T13:12:00");
+    assert_eq!(first["events"][0]["dst"], true);
+    assert_eq!(first["events"][1]["message"], "Voltage too low");
+    assert!(first["electricity"]["tariffs"].is_null());
+}
+
+#[test]
+fn test_json_and_csv_tariffs() {
+    let input = fs::read_to_string("examples/good/dual_tariff.dsmr").unwrap();
+    let config = parse_header(input.lines().next().unwrap()).unwrap();
+    let trees = parse_tree(&input).unwrap();
+    let json = json_value(&config, &trees).unwrap();
+
+    let tariffs = &json["telegrams"][1]["electricity"]["tariffs"];
+    assert_eq!(tariffs["consumed"][0], "101.500");
+    assert_eq!(tariffs["produced"][1], "6.000");
+    assert_eq!(json["telegrams"][1]["electricity"]["total_consumed"], "153.500");
+    assert_eq!(tariffs["active"], 2);
+
+    let csv = tariffs_to_csv(&tariff_series(&trees).unwrap()).unwrap();
+    assert_eq!(
+        csv,
+        "timestamp,utc,consumed_t1_kwh,consumed_t2_kwh,produced_t1_kwh,produced_t2_kwh,active_tariff\n\
+         1688536800,2023-07-05T06:00:00Z,1.500,2.000,0.000,1.000,2\n"
+    );
+}
+
+#[test]
+fn test_json_nested_gas() {
+    let input =
+        fs::read_to_string("examples/good_sequences/should_parse_1_recursive.dsmr").unwrap();
+    let config = parse_header(input.lines().next().unwrap()).unwrap();
+    let telegrams = parse_tree(&input).unwrap();
+    let json = json_value(&config, &telegrams).unwrap();
+
+    assert_eq!(json["gas"], true);
+    assert_eq!(json["recursive"], true);
+
+    let root = &json["telegrams"][0];
+    assert_eq!(root["timestamp"], telegrams[0].timestamp());
+    let gas = &root["children"][0]["children"][0];
+    assert_eq!(gas["information_type"], "G");
+    assert!(gas["electricity"].is_null());
+    assert!(gas["gas"]["total_delivered"].is_string());
+}
+
+#[test]
+fn test_json_water_and_heat() {
+    let input = fs::read_to_string("examples/good/water_heat.dsmr").unwrap();
+    let config = parse_header(input.lines().next().unwrap()).unwrap();
+    let json = json_value(&config, &parse_tree(&input).unwrap()).unwrap();
+
+    assert_eq!(json["utility"], true);
+    let water = &json["telegrams"][0];
+    assert_eq!(water["information_type"], "W");
+    assert_eq!(water["water"]["total_delivered"], "123.456");
+    assert!(water["heat"].is_null() && water["gas"].is_null());
+    // The declared decimals are kept
+    assert_eq!(json["telegrams"][1]["heat"]["total_delivered"], "45.120");
+}
+
+#[test]
+fn test_json_equipment_id() {
+    let input = fs::read_to_string("examples/good/multi_meter.dsmr").unwrap();
+    let config = parse_header(input.lines().next().unwrap()).unwrap();
+    let json = json_value(&config, &parse_tree(&input).unwrap()).unwrap();
+    assert_eq!(json["telegrams"][1]["equipment_id"], "E0026000000002");
+
+    let input = fs::read_to_string("examples/good/water_heat.dsmr").unwrap();
+    let json = json_value(&config, &parse_tree(&input).unwrap()).unwrap();
+    assert!(json["telegrams"][0]["equipment_id"].is_null());
+}
+
+#[test]
+fn test_to_json_is_valid_json() {
+    let input = fs::read_to_string("examples/good/simple_gas.dsmr").unwrap();
+    let config = parse_header(input.lines().next().unwrap()).unwrap();
+    let text = to_json(&config, &parse_tree(&input).unwrap()).unwrap();
+
+    let parsed: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(
+        parsed,
+        json_value(&config, &parse_tree(&input).unwrap()).unwrap()
+    );
+}
+
+fn sorted_telegrams(path: &str) -> Vec<dsmr_assignment::telegram::Telegram> {
+    let input = fs::read_to_string(path).unwrap();
+    sort_by_date(parse(&input).unwrap())
+}
+
+#[test]
+fn test_csv_voltages_and_currents() {
+    let telegrams = sorted_telegrams("examples/good/two_packets.dsmr");
+
+    // Both telegrams have the same date, so they are combined into one row
+    let voltages = voltages_to_csv(&voltage_phases(&telegrams)).unwrap();
+    let lines: Vec<&str> = voltages.lines().collect();
+    assert_eq!(lines[0], "timestamp,utc,phase_1_v,phase_2_v,phase_3_v");
+    assert_eq!(lines.len(), 2);
+    assert!(lines[1].ends_with(",242.7,241.6,242.92"), "{}", lines[1]);
+
+    let currents = currents_to_csv(&current_phases(&telegrams)).unwrap();
+    assert!(currents.starts_with("timestamp,utc,phase_1_a,phase_2_a,phase_3_a\n"));
+    assert_eq!(currents.lines().count(), 2);
+    assert!(currents.ends_with(",1,10,0.5\n"), "{currents}");
+}
+
+#[test]
+fn test_csv_one_row_per_timestamp() {
+    let telegrams = sorted_telegrams("examples/good_sequences/should_parse_0_recursive.dsmr");
+    for timestamps in [
+        voltage_phases(&telegrams)
+            .iter()
+            .map(|v| v.timestamp)
+            .collect_vec(),
+        current_phases(&telegrams)
+            .iter()
+            .map(|c| c.timestamp)
+            .collect_vec(),
+        gas_series(&telegrams)
+            .unwrap()
+            .iter()
+            .map(|g| g.timestamp)
+            .collect_vec(),
+    ] {
+        assert!(!timestamps.is_empty());
+        assert!(timestamps.iter().tuple_windows().all(|(a, b)| a < b));
+    }
+}
+
+#[test]
+fn test_csv_gas_and_energy() {
+    let telegrams = sorted_telegrams("examples/good_sequences/should_parse_0.dsmr");
+
+    let gas = gas_series(&telegrams).unwrap();
+    let csv = gas_to_csv(&gas).unwrap();
+    assert!(csv.starts_with("timestamp,utc,gas_delta_m3\n"));
+    assert_eq!(csv.lines().count(), gas.len() + 1);
+
+    let energy = energy_series(&telegrams).unwrap();
+    let csv = energy_to_csv(&energy).unwrap();
+    assert!(csv.starts_with("timestamp,utc,consumed_kwh,produced_kwh\n"));
+    // Unlike the plot, the CSV has every delta, not just the last 12
+    assert!(energy.len() > 12);
+    assert_eq!(csv.lines().count(), energy.len() + 1);
+}
+
+#[test]
+fn test_csv_phase_columns() {
+    let telegrams = sorted_telegrams("examples/good/single_phase.dsmr");
+    let voltages = voltages_to_csv(&voltage_phases(&telegrams)).unwrap();
+    assert_eq!(
+        voltages,
+        "timestamp,utc,phase_1_v\n\
+         1688563601,2023-07-05T13:26:41Z,231.2\n\
+         1688563611,2023-07-05T13:26:51Z,229.8\n"
+    );
+
+    let mixed = [
+        PhaseData {
+            timestamp: 1688563601,
+            phases: vec![230.0],
+        },
+        PhaseData {
+            timestamp: 1688563611,
+            phases: vec![230.0, 231.0],
+        },
+    ];
+    let csv = voltages_to_csv(&mixed).unwrap();
+    assert!(csv.contains("T13:26:41Z,230,\n"), "{csv}");
+}
+
+#[test]
+fn test_csv_utc_column() {
+    let gas = [GasData {
+        gas_delta: 0.5,
+        timestamp: 1688563601,
+    }];
+    assert_eq!(
+        gas_to_csv(&gas).unwrap(),
+        "timestamp,utc,gas_delta_m3\n1688563601,2023-07-05T13:26:41Z,0.5\n"
+    );
+}
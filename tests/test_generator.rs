@@ -0,0 +1,161 @@
+use dsmr_assignment::error::MainError;
+use dsmr_assignment::generator::*;
+use dsmr_assignment::helpers::*;
+use dsmr_assignment::parser::*;
+use dsmr_assignment::telegram::*;
+
+fn totals(telegrams: &[Telegram]) -> Vec<(Energy, Energy)> {
+    telegrams
+        .iter()
+        .filter_map(|t| match &t.data {
+            TelegramData::Electricity {
+                total_consumed,
+                total_produced,
+                ..
+            } => Some((*total_consumed, *total_produced)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_generate_is_deterministic() {
+    let config = GeneratorConfig {
+        seed: 42,
+        count: 50,
+        ..Default::default()
+    };
+    assert_eq!(
+        generate_text(&config).unwrap(),
+        generate_text(&config).unwrap()
+    );
+
+    let other = GeneratorConfig {
+        seed: 43,
+        ..config.clone()
+    };
+    assert_ne!(
+        generate_text(&config).unwrap(),
+        generate_text(&other).unwrap()
+    );
+}
+
+#[test]
+fn test_generate_parses_back() {
+    let config = GeneratorConfig {
+        seed: 7,
+        count: 200,
+        event_probability: 0.2,
+        ..Default::default()
+    };
+    let (_, generated) = generate(&config).unwrap();
+    let text = generate_text(&config).unwrap();
+
+    assert_eq!(parse_tree(&text).unwrap(), generated);
+    // One electricity and one gas telegram per step
+    assert_eq!(generated.len(), 400);
+    assert!(event_logs_encodable(&generated));
+}
+
+fn event_logs_encodable(telegrams: &[Telegram]) -> bool {
+    telegrams.iter().all(|t| {
+        t.base
+            .eventlog
+            .iter()
+            .all(|entry| encode_message(&entry.message).is_ok())
+    })
+}
+
+#[test]
+fn test_generate_counters_are_monotonic() {
+    let config = GeneratorConfig {
+        seed: 1,
+        count: 500,
+        ..Default::default()
+    };
+    let (_, telegrams) = generate(&config).unwrap();
+
+    let totals = totals(&telegrams);
+    assert_eq!(totals.len(), 500);
+    for pair in totals.windows(2) {
+        assert!(pair[1].0 >= pair[0].0);
+        assert!(pair[1].1 >= pair[0].1);
+    }
+}
+
+#[test]
+fn test_generate_nested() {
+    let config = GeneratorConfig {
+        count: 10,
+        is_recursive: true,
+        ..Default::default()
+    };
+    let text = generate_text(&config).unwrap();
+    assert!(text.starts_with("/v12\\+gr\n"));
+
+    let trees = parse_tree(&text).unwrap();
+    assert_eq!(trees.len(), 10);
+    assert!(trees
+        .iter()
+        .all(|t| matches!(t.children[0].data, TelegramData::Gas { .. })));
+}
+
+#[test]
+fn test_generate_v10() {
+    let config = GeneratorConfig {
+        count: 10,
+        version: (1, 0),
+        is_gas: false,
+        ..Default::default()
+    };
+    let text = generate_text(&config).unwrap();
+    assert!(text.starts_with("/v10\\\n"));
+    assert_eq!(parse(&text).unwrap().len(), 10);
+
+    let with_gas = GeneratorConfig {
+        is_gas: true,
+        ..config
+    };
+    assert!(generate(&with_gas).is_err());
+}
+
+#[test]
+fn test_generate_invalid_options_are_usage_errors() {
+    let nested_without_gas = GeneratorConfig {
+        is_gas: false,
+        is_recursive: true,
+        ..Default::default()
+    };
+    let zero_interval = GeneratorConfig {
+        interval: 0,
+        ..Default::default()
+    };
+    let v10_with_gas = GeneratorConfig {
+        version: (1, 0),
+        ..Default::default()
+    };
+    for config in [nested_without_gas, zero_interval, v10_with_gas] {
+        let Err(e) = generate(&config) else {
+            panic!("Expected an error");
+        };
+        assert!(matches!(e, MainError::UsageError(_)), "{e:?}");
+        assert_eq!(e.exit_code(), 1);
+    }
+}
+
+#[test]
+fn test_generate_summer_time() {
+    let config = GeneratorConfig {
+        count: 3,
+        // 2023-03-26 00:59:00 UTC, one minute before summer time starts
+        start: 1679792340,
+        ..Default::default()
+    };
+    let (_, telegrams) = generate(&config).unwrap();
+    let dates: Vec<(u8, bool)> = telegrams
+        .iter()
+        .map(|t| (t.base.date.hour, t.base.date.dst))
+        .step_by(2)
+        .collect();
+    assert_eq!(dates, vec![(1, false), (3, true), (3, true)]);
+}
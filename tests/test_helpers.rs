@@ -1,22 +1,34 @@
+#![allow(clippy::assertions_on_constants)]
+
 use tudelft_dsmr_output_generator::Graphs;
 
+use dsmr_assignment::decimal::Decimal;
+use dsmr_assignment::error::MainError;
 use dsmr_assignment::helpers::*;
 use dsmr_assignment::telegram::*;
 
 #[test]
 fn test_decode_message_simple() {
     let encoded = "48656c6c6f".to_string(); // "Hello" in hex
-    let result = decode_message(&encoded);
+    let result = decode_message(&encoded).unwrap();
     assert_eq!(result, "Hello");
 }
 
 #[test]
 fn test_decode_message_power_failure() {
     let encoded = "506f776572204661696c757265".to_string(); // "Power Failure" in hex
-    let result = decode_message(&encoded);
+    let result = decode_message(&encoded).unwrap();
     assert_eq!(result, "Power Failure");
 }
 
+#[test]
+fn test_encode_message_round_trip() {
+    let encoded = encode_message("Power Failure").unwrap();
+    assert_eq!(encoded, "506f776572204661696c757265");
+    assert_eq!(decode_message(&encoded).unwrap(), "Power Failure");
+    assert!(encode_message("Stroomstoring €").is_err());
+}
+
 #[test]
 fn test_process_voltages_single_telegram() {
     let telegram = create_test_electricity_telegram(
@@ -55,9 +67,9 @@ fn test_process_voltages_duplicate_timestamp() {
     );
 
     let voltages = process_voltages(&[telegram1, telegram2]);
-    assert_eq!(voltages.len(), 2);
+    assert_eq!(voltages.len(), 1);
     // Should take the higher voltage values for duplicate timestamps
-    assert!((voltages[1].phase_1 - 235.0).abs() < 0.001);
+    assert!((voltages[0].phase_1 - 235.0).abs() < 0.001);
 }
 #[test]
 
@@ -129,7 +141,7 @@ fn test_process_currents_multiple_telegrams() {
 fn test_process_gas_data_single_telegram() {
     let telegram = create_test_gas_telegram(1234567890, 12345.123);
 
-    let _gas_over_time = process_gas_data(&[telegram]);
+    let _gas_over_time = process_gas_data(&[telegram]).unwrap();
     // With only one telegram, no delta can be calculated
     assert!(true);
 }
@@ -139,7 +151,7 @@ fn test_process_gas_data_multiple_telegrams() {
     let telegram1 = create_test_gas_telegram(1234567890, 12345.123);
     let telegram2 = create_test_gas_telegram(2234567900, 22345.456);
 
-    let _gas_over_time = process_gas_data(&[telegram1, telegram2]);
+    let _gas_over_time = process_gas_data(&[telegram1, telegram2]).unwrap();
     // Should calculate delta between telegrams
     assert!(true);
 }
@@ -155,7 +167,7 @@ fn test_process_energy_data_single_telegram() {
         123.45,
     );
 
-    let _energy_over_time = process_energy_data(&[telegram]);
+    let _energy_over_time = process_energy_data(&[telegram]).unwrap();
     // With only one telegram, no delta can be calculated
     assert!(true);
 }
@@ -179,105 +191,163 @@ fn test_process_energy_data_multiple_telegrams() {
         124.45,   // +1.0 kWh produced
     );
 
-    let _energy_over_time = process_energy_data(&[telegram1, telegram2]);
+    let _energy_over_time = process_energy_data(&[telegram1, telegram2]).unwrap();
     // Should calculate delta between telegrams
     assert!(true);
 }
 
 #[test]
-fn test_process_voltages_with_none_values() {
-    let mut telegram = create_test_electricity_telegram(
-        1234567890,
-        [230.1, 231.2, 229.8],
-        [5.0, 6.0, 4.5],
-        [1.15, 1.38, 1.04],
-        12345.67,
-        123.45,
-    );
+fn test_phase_series_single_phase() {
+    let input = std::fs::read_to_string("examples/good/single_phase.dsmr").unwrap();
+    let mut telegrams = dsmr_assignment::parser::parse(&input).unwrap();
+    telegrams.reverse();
 
-    // Set voltage values to None
-    if let TelegramData::Electricity {
-        ref mut voltages, ..
-    } = telegram.data
-    {
-        voltages[0].value = None;
-        voltages[1].value = None;
-        voltages[2].value = None;
-    }
+    let voltages = voltage_phases(&telegrams);
+    assert_eq!(voltages.len(), 2);
+    assert_eq!(voltages[0].phases, vec![231.2]);
+    assert_eq!(current_phases(&telegrams)[1].phases, vec![6.0]);
+
+    // The plots always have three phases
+    let plotted = process_voltages(&telegrams);
+    assert_eq!(
+        (plotted[1].phase_1, plotted[1].phase_2, plotted[1].phase_3),
+        (229.8, 0.0, 0.0)
+    );
+}
 
-    let result = process_voltages(&[telegram]);
-    assert_eq!(result.len(), 0);
+#[test]
+fn test_water_and_heat_series() {
+    let input = std::fs::read_to_string("examples/good/water_heat.dsmr").unwrap();
+    let mut telegrams = dsmr_assignment::parser::parse(&input).unwrap();
+    telegrams.reverse();
+
+    let water = water_series(&telegrams).unwrap();
+    assert_eq!(water.len(), 1);
+    assert_eq!(water[0].water_delta, 62);
+    assert_eq!(water[0].timestamp, telegrams[3].timestamp());
+
+    let heat = heat_series(&telegrams).unwrap();
+    assert_eq!(heat.len(), 1);
+    assert_eq!(heat[0].heat_delta, Decimal::new(25, 2));
+
+    // Water and heat are not gas
+    assert!(gas_series(&telegrams).unwrap().is_empty());
+    let _result = process_water_data(&telegrams).unwrap();
+
+    // A counter that went down is left out rather than counted as no usage
+    let replaced = input.replace("00123.518*m3", "00000.012*m3");
+    let telegrams = dsmr_assignment::parser::parse(&replaced).unwrap();
+    assert!(water_series(&telegrams).unwrap().is_empty());
 }
 
 #[test]
-fn test_process_currents_with_none_values() {
-    let mut telegram = create_test_electricity_telegram(
-        1234567890,
-        [230.1, 231.2, 229.8],
-        [5.0, 6.0, 4.5],
-        [1.15, 1.38, 1.04],
-        12345.67,
-        123.45,
+fn test_power_failure_timeline() {
+    let input = std::fs::read_to_string("examples/good/power_quality.dsmr").unwrap();
+    let telegrams = dsmr_assignment::parser::parse(&input).unwrap();
+
+    // Both telegrams log the failure of July 4th, which is listed once
+    let timeline = power_failure_timeline(&telegrams);
+    let durations: Vec<u64> = timeline.iter().map(|event| event.duration).collect();
+    assert_eq!(durations, [240, 301, 185]);
+    assert_eq!(timeline[1].end - timeline[1].start, 301);
+    assert_eq!(
+        timeline[0].message(),
+        "Long power failure of 240 s, ended 2023-07-01 06:04:00 UTC"
     );
 
-    // Set current values to None
-    if let TelegramData::Electricity {
-        ref mut currents, ..
-    } = telegram.data
-    {
-        currents[0].value = None;
-        currents[1].value = None;
-        currents[2].value = None;
-    }
-
-    let _result = process_currents(&[telegram]);
-    assert!(true);
+    let mut result = Graphs::new().unwrap();
+    process_power_failures(&telegrams, &mut result);
+    let _ = result.generate();
 }
 
 #[test]
-fn test_process_gas_data_with_none_value() {
-    let mut telegram = create_test_gas_telegram(1234567890, 12345.123);
-
-    // Set gas value to None
-    if let TelegramData::Gas {
-        ref mut total_gas_delivered,
-    } = telegram.data
-    {
-        total_gas_delivered.value = None;
+fn test_event_timeline() {
+    let input = std::fs::read_to_string("examples/good/two_packets.dsmr").unwrap();
+    // The second telegram is sent an hour later and logs the same events
+    let (first, second) = input.split_at(input.rfind("\n2.1#(").unwrap());
+    let second = second.replacen("15:26:41", "16:26:41", 1);
+    let telegrams = dsmr_assignment::parser::parse(&format!("{first}{second}")).unwrap();
+
+    let timeline = event_timeline(&telegrams);
+    assert_eq!(timeline.len(), 2);
+    assert_eq!(timeline[0].severity, Severity::High);
+    assert_eq!(timeline[0].message, "Power Failure");
+    assert_eq!(timeline[0].date.timestamp, 1688296320);
+    assert_eq!(timeline[1].message, "Voltage too low");
+    for event in &timeline {
+        assert_eq!(event.first_seen, 1688563601);
+        assert_eq!(event.last_seen, 1688567201);
     }
 
-    let _result = process_gas_data(&[telegram]);
-    assert!(true);
+    // A different severity is a different event
+    let input = input.replacen("3.1.1#(H)", "3.1.1#(L)", 1);
+    let telegrams = dsmr_assignment::parser::parse(&input).unwrap();
+    let timeline = event_timeline(&telegrams);
+    assert_eq!(timeline.len(), 3);
+    assert_eq!(timeline[0].date, timeline[1].date);
+    assert_ne!(timeline[0].severity, timeline[1].severity);
+
+    let mut result = Graphs::new().unwrap();
+    process_event_logs(&telegrams, &mut result);
+    let _ = result.generate();
 }
 
 #[test]
-fn test_process_energy_data_with_none_values() {
-    let mut telegram = create_test_electricity_telegram(
-        1234567890,
-        [230.1, 231.2, 229.8],
-        [5.0, 6.0, 4.5],
-        [1.15, 1.38, 1.04],
-        12345.67,
-        123.45,
+fn test_energy_series_overflow_is_an_error() {
+    let input = std::fs::read_to_string("examples/good/two_packets.dsmr").unwrap();
+    let (first, second) = input.split_at(input.rfind("\n2.1#(").unwrap());
+    let first = first.replacen("7.4.1#(0011454892.*kWh)", "7.4.1#(.0000000001*kWh)", 1);
+    let second = second.replacen("15:26:41", "16:26:41", 1).replacen(
+        "7.4.1#(0012454892.*kWh)",
+        "7.4.1#(9999999999.*kWh)",
+        1,
     );
+    let telegrams = dsmr_assignment::parser::parse(&format!("{first}{second}")).unwrap();
+
+    // The difference needs more digits than a decimal has
+    assert!(matches!(
+        energy_series(&telegrams),
+        Err(MainError::DecimalOverflow(_))
+    ));
+    assert!(process_energy_data(&telegrams).is_err());
+}
 
-    // Set energy values to None
-    if let TelegramData::Electricity {
-        ref mut total_consumed,
-        ref mut total_produced,
-        ..
-    } = telegram.data
-    {
-        total_consumed.value = None;
-        total_produced.value = None;
-    }
+#[test]
+fn test_tariff_series() {
+    let input = std::fs::read_to_string("examples/good/dual_tariff.dsmr").unwrap();
+    let mut telegrams = dsmr_assignment::parser::parse(&input).unwrap();
+    telegrams.reverse();
+
+    let tariffs = tariff_series(&telegrams).unwrap();
+    assert_eq!(
+        tariffs,
+        vec![TariffEnergyData {
+            timestamp: telegrams[1].timestamp(),
+            consumed: [Decimal::new(15, 1), Decimal::new(2, 0)],
+            produced: [Decimal::ZERO, Decimal::new(1, 0)],
+            active_tariff: Tariff::Normal,
+        }]
+    );
 
-    let _result = process_energy_data(&[telegram]);
-    assert!(true);
+    // Together, the tariffs add up to the totals
+    let energy = energy_series(&telegrams).unwrap();
+    assert_eq!(energy[0].consumed, 3.5);
+    assert_eq!(energy[0].produced, 1.0);
+
+    // Telegrams without tariff counters are skipped
+    let single = vec![create_test_electricity_telegram(
+        1,
+        [230.0, 230.0, 230.0],
+        [1.0, 1.0, 1.0],
+        [0.1, 0.1, 0.1],
+        10.0,
+        1.0,
+    )];
+    assert!(tariff_series(&single).unwrap().is_empty());
 }
 
 fn create_test_electricity_telegram(
-    _timestamp: u64,
+    timestamp: u64,
     voltages: [f64; 3],
     currents: [f64; 3],
     powers: [f64; 3],
@@ -286,107 +356,28 @@ fn create_test_electricity_telegram(
 ) -> Telegram {
     Telegram::new(
         TelegramBase::new(
-            TelegramContent::new_value(
-                TelegramContentType::Start,
-                (1, 1, Some(0)),
-                Value::String("START".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::Date,
-                (2, 1, None),
-                Value::Date(Date::new(2023, 7, 5, 15, 26, 41, true)),
-                None,
-            ),
-            vec![],
+            Marker(Some(0)),
+            Date::from_timestamp(timestamp as i64).unwrap(),
             vec![],
-            vec![],
-            TelegramContent::new_value(
-                TelegramContentType::InformationType,
-                (4, 1, None),
-                Value::String("E".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::End,
-                (1, 2, Some(0)),
-                Value::String("END".to_string()),
-                None,
-            ),
+            InformationType::Electricity,
+            Marker(Some(0)),
         ),
         TelegramData::Electricity {
-            voltages: [
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(1)),
-                    Value::Float(voltages[0]),
-                    Some(TelegramContentUnit::V),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(2)),
-                    Value::Float(voltages[1]),
-                    Some(TelegramContentUnit::V),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(3)),
-                    Value::Float(voltages[2]),
-                    Some(TelegramContentUnit::V),
-                ),
-            ],
-            currents: [
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(1)),
-                    Value::Float(currents[0]),
-                    Some(TelegramContentUnit::A),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(2)),
-                    Value::Float(currents[1]),
-                    Some(TelegramContentUnit::A),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(3)),
-                    Value::Float(currents[2]),
-                    Some(TelegramContentUnit::A),
-                ),
+            voltages: vec![
+                Voltage(voltages[0]),
+                Voltage(voltages[1]),
+                Voltage(voltages[2]),
             ],
-            powers: [
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(1)),
-                    Value::Float(powers[0]),
-                    Some(TelegramContentUnit::KW),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(2)),
-                    Value::Float(powers[1]),
-                    Some(TelegramContentUnit::KW),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(3)),
-                    Value::Float(powers[2]),
-                    Some(TelegramContentUnit::KW),
-                ),
+            currents: vec![
+                Current(currents[0]),
+                Current(currents[1]),
+                Current(currents[2]),
             ],
-            total_consumed: TelegramContent::new_value(
-                TelegramContentType::TotalConsumed,
-                (7, 4, Some(1)),
-                Value::Float(total_consumed),
-                Some(TelegramContentUnit::KWH),
-            ),
-            total_produced: TelegramContent::new_value(
-                TelegramContentType::TotalProduced,
-                (7, 4, Some(2)),
-                Value::Float(total_produced),
-                Some(TelegramContentUnit::KWH),
-            ),
+            powers: vec![Power(powers[0]), Power(powers[1]), Power(powers[2])],
+            total_consumed: Energy(Decimal::from_f64(total_consumed).unwrap()),
+            total_produced: Energy(Decimal::from_f64(total_produced).unwrap()),
+            tariffs: None,
+            quality: None,
         },
     )
 }
@@ -394,41 +385,14 @@ fn create_test_electricity_telegram(
 fn create_test_gas_telegram(_timestamp: u64, gas_total: f64) -> Telegram {
     Telegram::new(
         TelegramBase::new(
-            TelegramContent::new_value(
-                TelegramContentType::Start,
-                (1, 1, Some(1)),
-                Value::String("START".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::Date,
-                (2, 1, None),
-                Value::Date(Date::new(2023, 7, 5, 15, 26, 41, true)),
-                None,
-            ),
-            vec![],
-            vec![],
+            Marker(Some(1)),
+            Date::new(2023, 7, 5, 15, 26, 41, true).unwrap(),
             vec![],
-            TelegramContent::new_value(
-                TelegramContentType::InformationType,
-                (4, 1, None),
-                Value::String("G".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::End,
-                (1, 2, Some(1)),
-                Value::String("END".to_string()),
-                None,
-            ),
+            InformationType::Gas,
+            Marker(Some(1)),
         ),
         TelegramData::Gas {
-            total_gas_delivered: TelegramContent::new_value(
-                TelegramContentType::GasTotalDelivered,
-                (5, 2, None),
-                Value::Float(gas_total),
-                Some(TelegramContentUnit::M3),
-            ),
+            total_gas_delivered: Volume(Decimal::from_f64(gas_total).unwrap()),
         },
     )
 }
@@ -436,7 +400,7 @@ fn create_test_gas_telegram(_timestamp: u64, gas_total: f64) -> Telegram {
 #[test]
 fn test_process_event_logs_high_severity() {
     let mut result = Graphs::new().unwrap();
-    let telegram = create_test_telegram_with_eventlog(1, "H".to_string(), "48656c6c6f".to_string());
+    let telegram = create_test_telegram_with_eventlog(1, Severity::High, "Hello".to_string());
     process_event_logs(&[telegram], &mut result);
     let _freaking_library_that_forces_you_to_use_a_function_prior_to_dropping = result.generate();
     assert!(true);
@@ -445,7 +409,7 @@ fn test_process_event_logs_high_severity() {
 #[test]
 fn test_process_event_logs_low_severity() {
     let mut result = Graphs::new().unwrap();
-    let telegram = create_test_telegram_with_eventlog(1, "L".to_string(), "576f726c64".to_string());
+    let telegram = create_test_telegram_with_eventlog(1, Severity::Low, "World".to_string());
     process_event_logs(&[telegram], &mut result);
     let _freaking_library_that_forces_you_to_use_a_function_prior_to_dropping = result.generate();
     assert!(true);
@@ -476,7 +440,7 @@ fn test_process_gas_data_with_electricity_telegrams() {
         12345.67,
         123.45,
     );
-    let _gas_over_time = process_gas_data(&[electricity_telegram]);
+    let _gas_over_time = process_gas_data(&[electricity_telegram]).unwrap();
     // Should handle gracefully with no gas data
     assert!(true);
 }
@@ -484,143 +448,97 @@ fn test_process_gas_data_with_electricity_telegrams() {
 #[test]
 fn test_process_energy_data_with_gas_telegrams() {
     let gas_telegram = create_test_gas_telegram(1234567890, 12345.123);
-    let _energy_over_time = process_energy_data(&[gas_telegram]);
+    let _energy_over_time = process_energy_data(&[gas_telegram]).unwrap();
     // Should handle gracefully with no energy data
     assert!(true);
 }
 
 fn create_test_telegram_with_eventlog(
     event_id: u32,
-    severity: String,
+    severity: Severity,
     message: String,
 ) -> Telegram {
     Telegram::new(
         TelegramBase::new(
-            TelegramContent::new_value(
-                TelegramContentType::Start,
-                (1, 1, Some(0)),
-                Value::String("START".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::Date,
-                (2, 1, None),
-                Value::Date(Date::new(2023, 7, 5, 15, 26, 41, true)),
-                None,
-            ),
-            vec![(
-                event_id,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogSeverity,
-                    (3, 1, Some(event_id)),
-                    Value::String(severity),
-                    None,
-                ),
-            )],
-            vec![(
-                event_id,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogMessage,
-                    (3, 2, Some(event_id)),
-                    Value::String(message),
-                    None,
-                ),
-            )],
-            vec![(
-                event_id,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogDate,
-                    (3, 3, Some(event_id)),
-                    Value::Date(Date::new(2023, 7, 2, 13, 12, 0, true)),
-                    None,
-                ),
-            )],
-            TelegramContent::new_value(
-                TelegramContentType::InformationType,
-                (4, 1, None),
-                Value::String("E".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::End,
-                (1, 2, Some(0)),
-                Value::String("END".to_string()),
-                None,
-            ),
+            Marker(Some(0)),
+            Date::new(2023, 7, 5, 15, 26, 41, true).unwrap(),
+            vec![EventLogEntry {
+                index: event_id,
+                severity,
+                message,
+                date: Date::new(2023, 7, 2, 13, 12, 0, true).unwrap(),
+            }],
+            InformationType::Electricity,
+            Marker(Some(0)),
         ),
         TelegramData::Electricity {
-            voltages: [
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(1)),
-                    Value::Float(230.1),
-                    Some(TelegramContentUnit::V),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(2)),
-                    Value::Float(231.2),
-                    Some(TelegramContentUnit::V),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(3)),
-                    Value::Float(229.8),
-                    Some(TelegramContentUnit::V),
-                ),
-            ],
-            currents: [
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(1)),
-                    Value::Float(5.0),
-                    Some(TelegramContentUnit::A),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(2)),
-                    Value::Float(6.0),
-                    Some(TelegramContentUnit::A),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(3)),
-                    Value::Float(4.5),
-                    Some(TelegramContentUnit::A),
-                ),
-            ],
-            powers: [
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(1)),
-                    Value::Float(1.15),
-                    Some(TelegramContentUnit::KW),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(2)),
-                    Value::Float(1.38),
-                    Some(TelegramContentUnit::KW),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(3)),
-                    Value::Float(1.04),
-                    Some(TelegramContentUnit::KW),
-                ),
-            ],
-            total_consumed: TelegramContent::new_value(
-                TelegramContentType::TotalConsumed,
-                (7, 4, Some(1)),
-                Value::Float(12345.67),
-                Some(TelegramContentUnit::KWH),
-            ),
-            total_produced: TelegramContent::new_value(
-                TelegramContentType::TotalProduced,
-                (7, 4, Some(2)),
-                Value::Float(123.45),
-                Some(TelegramContentUnit::KWH),
-            ),
+            voltages: vec![Voltage(230.1), Voltage(231.2), Voltage(229.8)],
+            currents: vec![Current(5.0), Current(6.0), Current(4.5)],
+            powers: vec![Power(1.15), Power(1.38), Power(1.04)],
+            total_consumed: Energy(Decimal::new(1234567, 2)),
+            total_produced: Energy(Decimal::new(12345, 2)),
+            tariffs: None,
+            quality: None,
         },
     )
 }
+
+#[test]
+fn test_group_by_meter() {
+    let input = std::fs::read_to_string("examples/good/multi_meter.dsmr").unwrap();
+    let mut telegrams = dsmr_assignment::parser::parse(&input).unwrap();
+    telegrams.reverse();
+
+    let meters = group_by_meter(telegrams);
+    assert_eq!(meters.len(), 2);
+    assert_eq!(meters[0].equipment_id.as_deref(), Some("E0026000000001"));
+    assert_eq!(meters[1].equipment_id.as_deref(), Some("E0026000000002"));
+    assert!(meters.iter().all(|meter| meter.telegrams.len() == 2));
+
+    // The series of one meter are not mixed with those of the other
+    let energy = per_meter(&meters, energy_series);
+    assert_eq!(energy.len(), 2);
+    assert_eq!(energy[0].0.as_deref(), Some("E0026000000001"));
+    assert!((energy[0].1.as_ref().unwrap()[0].consumed - 0.003).abs() < 1e-9);
+    assert!((energy[1].1.as_ref().unwrap()[0].consumed - 0.002).abs() < 1e-9);
+
+    let voltages = per_meter(&meters, voltage_phases);
+    assert_eq!(voltages[1].1[0].phases, [229.4]);
+
+    let mut result = Graphs::new().unwrap();
+    per_meter(&meters, |telegrams| {
+        process_event_logs(telegrams, &mut result)
+    });
+    let _ = result.generate();
+}
+
+#[test]
+fn test_group_by_meter_without_ids() {
+    let input = std::fs::read_to_string("examples/good/single_phase.dsmr").unwrap();
+    let telegrams = dsmr_assignment::parser::parse(&input).unwrap();
+
+    let meters = group_by_meter(telegrams);
+    assert_eq!(meters.len(), 1);
+    assert_eq!(meters[0].equipment_id, None);
+    assert_eq!(meters[0].telegrams.len(), 2);
+}
+
+#[test]
+fn test_series_keep_the_repeated_hour_apart() {
+    // When summer time ends, 02:00 - 03:00 happens twice: first in summer, then in winter time
+    let input = std::fs::read_to_string("examples/good/fall_back.dsmr").unwrap();
+    let mut telegrams = dsmr_assignment::parser::parse(&input).unwrap();
+    telegrams.reverse();
+
+    let voltages = voltage_phases(&telegrams);
+    let timestamps: Vec<_> = voltages.iter().map(|v| v.timestamp).collect();
+    assert_eq!(timestamps, [1698537600, 1698539400, 1698541200, 1698543000]);
+    assert_eq!(voltages[2].phases, [229.9]);
+
+    let energy = energy_series(&telegrams).unwrap();
+    assert_eq!(energy.len(), 3);
+    for (delta, timestamp) in energy.iter().zip(&timestamps[1..]) {
+        assert_eq!(delta.timestamp, *timestamp);
+        assert!((delta.consumed - 0.1).abs() < 1e-9);
+    }
+}
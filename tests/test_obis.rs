@@ -0,0 +1,281 @@
+use std::fs;
+
+use dsmr_assignment::decimal::Decimal;
+use dsmr_assignment::helpers::process_voltages;
+use dsmr_assignment::obis::*;
+use dsmr_assignment::parser::{parse, parse_header, parse_tree_with, ParseOptions};
+use dsmr_assignment::serializer::serialize_header;
+use dsmr_assignment::telegram::*;
+
+/// The example telegram of the DSMR 5.0.2 P1 companion standard, as a meter sends it
+const DSMR5_TELEGRAM: &str = r"/ISk5\2MT382-1000
+
+1-3:0.2.8(50)
+0-0:1.0.0(101209113020W)
+0-0:96.1.1(4B384547303034303436333935353037)
+1-0:1.8.1(123456.789*kWh)
+1-0:1.8.2(123456.789*kWh)
+1-0:2.8.1(123456.789*kWh)
+1-0:2.8.2(123456.789*kWh)
+0-0:96.14.0(0002)
+1-0:1.7.0(01.193*kW)
+1-0:2.7.0(00.000*kW)
+0-0:96.7.21(00004)
+0-0:96.7.9(00002)
+1-0:99.97.0(2)(0-0:96.7.19)(101208152415W)(0000000240*s)(101208151004W)(0000000301*s)
+1-0:32.32.0(00002)
+1-0:52.32.0(00001)
+1-0:72.32.0(00000)
+1-0:32.36.0(00000)
+1-0:52.36.0(00003)
+1-0:72.36.0(00000)
+0-0:96.13.0(303132333435363738393A3B3C3D3E3F303132333435363738393A3B3C3D3E3F303132333435363738393A3B3C3D3E3F303132333435363738393A3B3C3D3E3F303132333435363738393A3B3C3D3E3F)
+1-0:32.7.0(220.1*V)
+1-0:52.7.0(220.2*V)
+1-0:72.7.0(220.3*V)
+1-0:31.7.0(001*A)
+1-0:51.7.0(002*A)
+1-0:71.7.0(003*A)
+1-0:21.7.0(01.111*kW)
+1-0:41.7.0(02.222*kW)
+1-0:61.7.0(03.333*kW)
+1-0:22.7.0(04.444*kW)
+1-0:42.7.0(05.555*kW)
+1-0:62.7.0(06.666*kW)
+0-1:24.1.0(003)
+0-1:96.1.0(3232323241424344313233343536373839)
+0-1:24.2.1(101209112500W)(12785.123*m3)
+!EF2F";
+
+#[test]
+fn test_header_selects_dialect() {
+    assert_eq!(parse_header("/v12\\").unwrap().dialect, Dialect::Simplified);
+    assert_eq!(parse_header("/v12\\+o").unwrap().dialect, Dialect::Obis);
+
+    let config = parse_header("/v12\\+ogr").unwrap();
+    assert_eq!(config.dialect, Dialect::Obis);
+    assert!(config.is_gas && config.is_recursive);
+    assert_eq!(serialize_header(&config), "/v12\\+gro");
+
+    assert!(parse_header("/v10\\+o").is_err());
+    assert!(parse_header("/v12\\+oo").is_err());
+    assert!(parse_header("/v12\\+").is_err());
+}
+
+#[test]
+fn test_mapping_table() {
+    let voltage = lookup("1-0:32.7.0").unwrap();
+    assert_eq!(voltage.content_type, TelegramContentType::Voltage);
+    assert_eq!(voltage.id, (7, 1, Some(1)));
+
+    assert_eq!(
+        lookup("1-0:1.8.0").unwrap().content_type,
+        TelegramContentType::TotalConsumed
+    );
+    let tariff_2 = lookup("1-0:1.8.2").unwrap();
+    assert_eq!(tariff_2.content_type, TelegramContentType::TariffConsumed);
+    assert_eq!(tariff_2.id, (7, 5, Some(2)));
+    assert_eq!(
+        lookup("0-1:24.2.1").unwrap().content_type,
+        TelegramContentType::GasTotalDelivered
+    );
+    assert!(lookup("1-0:99.99.9").is_none());
+
+    // Every mapped id must be understood by the rest of the parser
+    for obis in &OBIS_CODES {
+        assert_eq!(
+            dsmr_assignment::parser::determine_content_type(&obis.id).unwrap(),
+            obis.content_type,
+            "{}",
+            obis.code
+        );
+    }
+}
+
+#[test]
+fn test_parse_obis_line() {
+    let voltage = parse_obis_line("1-0:52.7.0(231.4*V)").unwrap();
+    assert_eq!(voltage.telegram_content_type, TelegramContentType::Voltage);
+    assert_eq!(voltage.id, (7, 1, Some(2)));
+    assert_eq!(voltage.value, Some(Value::Number(Decimal::new(2314, 1))));
+    assert_eq!(voltage.unit, Some(TelegramContentUnit::V));
+
+    let tariff = parse_obis_line("0-0:96.14.0(0002)").unwrap();
+    assert_eq!(tariff.value, Some(Value::String("0002".to_string())));
+
+    let gas = parse_obis_line("0-1:24.2.1(230705150000S)(12345.678*m3)").unwrap();
+    assert_eq!(gas.value, Some(Value::Number(Decimal::new(12345678, 3))));
+
+    let date = parse_obis_line("0-0:1.0.0(230705152641S)").unwrap();
+    match date.value {
+        Some(Value::Date(date)) => {
+            assert_eq!(
+                (
+                    date.year,
+                    date.month,
+                    date.day,
+                    date.hour,
+                    date.minute,
+                    date.seconds
+                ),
+                (2023, 7, 5, 15, 26, 41)
+            );
+            assert!(date.dst);
+        }
+        other => panic!("Expected a date, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_obis_line_errors() {
+    for bad in [
+        "1-0:99.7.0(230.1*V)",
+        "1-0:32.7.0(230.12*V)",
+        "1-0:32.7.0(230.1*A)",
+        "1-0:32.7.0(230.1*V",
+        "1-0:31.7.0(1*A)",
+        "0-0:1.0.0(230705152641X)",
+        "0-0:1.0.0(231305152641S)",
+        "0-0:96.14.0(0003)",
+    ] {
+        assert!(parse_obis_line(bad).is_err(), "{bad}");
+    }
+    assert!(is_obis_line("1-0:32.7.0(230.1*V)"));
+    assert!(!is_obis_line("7.1.1#(0230.1*V)"));
+}
+
+#[test]
+fn test_parse_obis_example() {
+    let input = fs::read_to_string("examples/good/obis.dsmr").unwrap();
+    let telegrams = parse(&input).unwrap();
+    assert_eq!(telegrams.len(), 2);
+
+    let voltages = process_voltages(&telegrams);
+    assert_eq!(voltages.len(), 1);
+    assert_eq!(
+        (
+            voltages[0].phase_1,
+            voltages[0].phase_2,
+            voltages[0].phase_3
+        ),
+        (230.1, 231.4, 229.8)
+    );
+
+    let gas = telegrams
+        .iter()
+        .find(|t| matches!(t.data, TelegramData::Gas { .. }))
+        .unwrap();
+    assert_eq!(gas.timestamp(), telegrams[0].timestamp());
+
+    // The electricity and gas meters identify themselves with their own codes
+    assert_eq!(gas.base.equipment_id.as_deref(), Some("G0031000000002"));
+    let electricity = telegrams
+        .iter()
+        .find(|t| t.base.equipment_id.as_deref() == Some("E0026000000001"));
+    // Like real meters, the example only has the tariff counters
+    match &electricity.unwrap().data {
+        TelegramData::Electricity {
+            total_consumed,
+            total_produced,
+            ..
+        } => {
+            assert_eq!(*total_consumed, Energy(Decimal::new(1234567, 3)));
+            assert_eq!(*total_produced, Energy(Decimal::new(123456, 3)));
+        }
+        other => panic!("Expected electricity, got {other:?}"),
+    }
+    assert!(parse_obis_line("0-0:96.1.1(4530zz)").is_err());
+
+    // Two digit years follow the pivot of the parse options
+    let options = ParseOptions {
+        year_pivot: YearPivot(20),
+        ..ParseOptions::default()
+    };
+    let (telegrams, _) = parse_tree_with(&input, &options).unwrap();
+    assert_eq!(telegrams[0].base.date.year, 1923);
+}
+
+#[test]
+fn test_dialect_from_options() {
+    let input = fs::read_to_string("examples/good/obis.dsmr")
+        .unwrap()
+        .replace("+gro", "+gr");
+    // Without the dialect, OBIS codes are not understood
+    assert!(parse(&input).is_err());
+
+    let options = ParseOptions {
+        dialect: Some(Dialect::Obis),
+        ..ParseOptions::default()
+    };
+    let (telegrams, _) = parse_tree_with(&input, &options).unwrap();
+    assert_eq!(telegrams.len(), 1);
+    assert_eq!(telegrams[0].children.len(), 1);
+
+    assert_eq!(Dialect::try_from("obis").unwrap(), Dialect::Obis);
+    assert!(Dialect::try_from("dlms").is_err());
+}
+
+#[test]
+fn test_dsmr5_telegram() {
+    let lines: Vec<&str> = DSMR5_TELEGRAM.lines().collect();
+    // Every line between the identification line and the footer is understood
+    let body = &lines[2..lines.len() - 1];
+    for line in body {
+        assert!(parse_obis_contents(line).is_ok(), "{line}");
+    }
+    let content_count = |prefix: &str| {
+        let line = body.iter().find(|line| line.starts_with(prefix)).unwrap();
+        parse_obis_contents(line).unwrap().len()
+    };
+    assert_eq!(content_count("1-0:1.7.0"), 0);
+    assert_eq!(content_count("0-0:96.13.0"), 0);
+    assert_eq!(content_count(FAILURE_LOG_CODE), 4);
+    assert!(parse_obis_line("1-0:1.7.0(01.193*kW)").is_err());
+
+    // Its electricity lines make up an electricity telegram
+    let electricity: Vec<&str> = body
+        .iter()
+        .filter(|line| !line.starts_with("0-1:"))
+        .copied()
+        .collect();
+    let input = format!(
+        "/v12\\+o\n1.1.0#(START)\n4.1#(E)\n{}\n1.2.0#(END)\n",
+        electricity.join("\n")
+    );
+    let telegrams = parse(&input).unwrap();
+    match &telegrams[0].data {
+        TelegramData::Electricity {
+            total_consumed,
+            powers,
+            quality: Some(quality),
+            ..
+        } => {
+            assert_eq!(*total_consumed, Energy(Decimal::new(246913578, 3)));
+            // Each phase produces more than it consumes
+            assert_eq!(powers, &[Power(-3.333), Power(-3.333), Power(-3.333)]);
+            assert_eq!(
+                (quality.power_failures, quality.long_power_failures),
+                (4, 2)
+            );
+            let durations: Vec<u64> = quality.failure_log.iter().map(|f| f.duration).collect();
+            assert_eq!(durations, [240, 301]);
+            assert_eq!(quality.voltage_swells, [0, 3, 0]);
+        }
+        other => panic!("Expected electricity with power quality, got {other:?}"),
+    }
+
+    let gas = parse_obis_contents(body[body.len() - 1]).unwrap();
+    assert_eq!(gas[0].value, Some(Value::Number(Decimal::new(12785123, 3))));
+
+    // The log must have as many entries as it says
+    for bad in [
+        "1-0:99.97.0(2)(0-0:96.7.19)(101208152415W)(0000000240*s)",
+        "1-0:99.97.0(1)(0-0:96.7.21)(101208152415W)(0000000240*s)",
+        "1-0:99.97.0(1)(0-0:96.7.19)(101208152415W)(240*s)",
+    ] {
+        assert!(parse_obis_contents(bad).is_err(), "{bad}");
+    }
+    assert!(parse_obis_contents("1-0:99.97.0(0)(0-0:96.7.19)")
+        .unwrap()
+        .is_empty());
+}
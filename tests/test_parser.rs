@@ -1,3 +1,5 @@
+use dsmr_assignment::decimal::Decimal;
+use dsmr_assignment::error::MainError;
 use dsmr_assignment::parser::*;
 use dsmr_assignment::telegram::*;
 use std::fs;
@@ -7,143 +9,34 @@ fn test_parse_reorder() {
     let input =
         fs::read_to_string("examples/good/reorder.dsmr").expect("Failed to read reorder.dsmr file");
 
-    let expected = vec![Telegram::new(
+    let expected = [Telegram::new(
         TelegramBase::new(
-            TelegramContent::new_value(
-                TelegramContentType::Start,
-                (1, 1, Some(0)),
-                Value::String("START".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::Date,
-                (2, 1, None),
-                Value::Date(Date::new(2023, 7, 5, 15, 26, 41, true)),
-                None,
-            ),
-            vec![(
-                1,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogSeverity,
-                    (3, 1, Some(1)),
-                    Value::String("H".to_string()),
-                    None,
-                ),
-            )],
-            vec![(
-                1,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogMessage,
-                    (3, 2, Some(1)),
-                    Value::String("506f776572204661696c757265".to_string()),
-                    None,
-                ),
-            )],
-            vec![(
-                1,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogDate,
-                    (3, 3, Some(1)),
-                    Value::Date(Date::new(2023, 7, 2, 13, 12, 0, true)),
-                    None,
-                ),
-            )],
-            TelegramContent::new_value(
-                TelegramContentType::InformationType,
-                (4, 1, None),
-                Value::String("E".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::End,
-                (1, 2, Some(0)),
-                Value::String("END".to_string()),
-                None,
-            ),
+            Marker(Some(0)),
+            Date::new(2023, 7, 5, 15, 26, 41, true).unwrap(),
+            vec![EventLogEntry {
+                index: 1,
+                severity: Severity::High,
+                message: "Power Failure".to_string(),
+                date: Date::new(2023, 7, 2, 13, 12, 0, true).unwrap(),
+            }],
+            InformationType::Electricity,
+            Marker(Some(0)),
         ),
         TelegramData::Electricity {
-            voltages: [
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(1)),
-                    Value::Float(241.7),
-                    Some(TelegramContentUnit::V),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(2)),
-                    Value::Float(240.6),
-                    Some(TelegramContentUnit::V),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(3)),
-                    Value::Float(241.92),
-                    Some(TelegramContentUnit::V),
-                ),
-            ],
-            currents: [
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(1)),
-                    Value::Float(1.0),
-                    Some(TelegramContentUnit::A),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(2)),
-                    Value::Float(10.0),
-                    Some(TelegramContentUnit::A),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(3)),
-                    Value::Float(0.5),
-                    Some(TelegramContentUnit::A),
-                ),
-            ],
-            powers: [
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(1)),
-                    Value::Float(1.00),
-                    Some(TelegramContentUnit::KW),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(2)),
-                    Value::Float(-5.010),
-                    Some(TelegramContentUnit::KW),
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(3)),
-                    Value::Float(2.500),
-                    Some(TelegramContentUnit::KW),
-                ),
-            ],
-            total_consumed: TelegramContent::new_value(
-                TelegramContentType::TotalConsumed,
-                (7, 4, Some(1)),
-                Value::Float(11454892.0),
-                Some(TelegramContentUnit::KWH),
-            ),
-            total_produced: TelegramContent::new_value(
-                TelegramContentType::TotalProduced,
-                (7, 4, Some(2)),
-                Value::Float(1245.0),
-                Some(TelegramContentUnit::KWH),
-            ),
+            voltages: vec![Voltage(241.7), Voltage(240.6), Voltage(241.92)],
+            currents: vec![Current(1.0), Current(10.0), Current(0.5)],
+            powers: vec![Power(1.00), Power(-5.010), Power(2.500)],
+            total_consumed: Energy(Decimal::new(114548920, 1)),
+            total_produced: Energy(Decimal::new(12450, 1)),
+            tariffs: None,
+            quality: None,
         },
     )];
 
     let parsed = parse(&input).expect("Failed to parse reorder example");
     assert_eq!(parsed.len(), expected.len());
 
-    assert_eq!(
-        parsed[0].base.start.telegram_content_type,
-        expected[0].base.start.telegram_content_type
-    );
+    assert_eq!(parsed[0].base.start, expected[0].base.start);
     match (&parsed[0].data, &expected[0].data) {
         (TelegramData::Electricity { .. }, TelegramData::Electricity { .. }) => {
             // Electricity data matched, the '..' rest operator ignores all the fields
@@ -157,174 +50,41 @@ fn test_parse_simple_gas() {
     let input = fs::read_to_string("examples/good/simple_gas.dsmr")
         .expect("Failed to read simple_gas.dsmr file");
 
-    let expected = vec![
+    let expected = [
         Telegram::new(
             TelegramBase::new(
-                TelegramContent::new_value(
-                    TelegramContentType::Start,
-                    (1, 1, Some(0)),
-                    Value::String("START".to_string()),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Date,
-                    (2, 1, None),
-                    Value::Date(Date::new(2023, 7, 5, 15, 26, 41, true)),
-                    None,
-                ),
-                vec![(
-                    1,
-                    TelegramContent::new_value(
-                        TelegramContentType::EventlogSeverity,
-                        (3, 1, Some(1)),
-                        Value::String("H".to_string()),
-                        None,
-                    ),
-                )],
-                vec![(
-                    1,
-                    TelegramContent::new_value(
-                        TelegramContentType::EventlogMessage,
-                        (3, 2, Some(1)),
-                        Value::String("506f776572204661696c757265".to_string()),
-                        None,
-                    ),
-                )],
-                vec![(
-                    1,
-                    TelegramContent::new_value(
-                        TelegramContentType::EventlogDate,
-                        (3, 3, Some(1)),
-                        Value::Date(Date::new(2023, 7, 2, 13, 12, 0, true)),
-                        None,
-                    ),
-                )],
-                TelegramContent::new_value(
-                    TelegramContentType::InformationType,
-                    (4, 1, None),
-                    Value::String("E".to_string()),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::End,
-                    (1, 2, Some(0)),
-                    Value::String("END".to_string()),
-                    None,
-                ),
+                Marker(Some(0)),
+                Date::new(2023, 7, 5, 15, 26, 41, true).unwrap(),
+                vec![EventLogEntry {
+                    index: 1,
+                    severity: Severity::High,
+                    message: "Power Failure".to_string(),
+                    date: Date::new(2023, 7, 2, 13, 12, 0, true).unwrap(),
+                }],
+                InformationType::Electricity,
+                Marker(Some(0)),
             ),
             TelegramData::Electricity {
-                voltages: [
-                    TelegramContent::new_value(
-                        TelegramContentType::Voltage,
-                        (7, 1, Some(1)),
-                        Value::Float(241.7),
-                        Some(TelegramContentUnit::V),
-                    ),
-                    TelegramContent::new_value(
-                        TelegramContentType::Voltage,
-                        (7, 1, Some(2)),
-                        Value::Float(240.6),
-                        Some(TelegramContentUnit::V),
-                    ),
-                    TelegramContent::new_value(
-                        TelegramContentType::Voltage,
-                        (7, 1, Some(3)),
-                        Value::Float(241.92),
-                        Some(TelegramContentUnit::V),
-                    ),
-                ],
-                currents: [
-                    TelegramContent::new_value(
-                        TelegramContentType::Current,
-                        (7, 2, Some(1)),
-                        Value::Float(1.0),
-                        Some(TelegramContentUnit::A),
-                    ),
-                    TelegramContent::new_value(
-                        TelegramContentType::Current,
-                        (7, 2, Some(2)),
-                        Value::Float(10.0),
-                        Some(TelegramContentUnit::A),
-                    ),
-                    TelegramContent::new_value(
-                        TelegramContentType::Current,
-                        (7, 2, Some(3)),
-                        Value::Float(0.5),
-                        Some(TelegramContentUnit::A),
-                    ),
-                ],
-                powers: [
-                    TelegramContent::new_value(
-                        TelegramContentType::Power,
-                        (7, 3, Some(1)),
-                        Value::Float(1.00),
-                        Some(TelegramContentUnit::KW),
-                    ),
-                    TelegramContent::new_value(
-                        TelegramContentType::Power,
-                        (7, 3, Some(2)),
-                        Value::Float(-5.010),
-                        Some(TelegramContentUnit::KW),
-                    ),
-                    TelegramContent::new_value(
-                        TelegramContentType::Power,
-                        (7, 3, Some(3)),
-                        Value::Float(2.500),
-                        Some(TelegramContentUnit::KW),
-                    ),
-                ],
-                total_consumed: TelegramContent::new_value(
-                    TelegramContentType::TotalConsumed,
-                    (7, 4, Some(1)),
-                    Value::Float(11454892.0),
-                    Some(TelegramContentUnit::KWH),
-                ),
-                total_produced: TelegramContent::new_value(
-                    TelegramContentType::TotalProduced,
-                    (7, 4, Some(2)),
-                    Value::Float(1245.0),
-                    Some(TelegramContentUnit::KWH),
-                ),
+                voltages: vec![Voltage(241.7), Voltage(240.6), Voltage(241.92)],
+                currents: vec![Current(1.0), Current(10.0), Current(0.5)],
+                powers: vec![Power(1.00), Power(-5.010), Power(2.500)],
+                total_consumed: Energy(Decimal::new(114548920, 1)),
+                total_produced: Energy(Decimal::new(12450, 1)),
+                tariffs: None,
+                quality: None,
             },
         ),
         // Gas recursive inner telegram
         Telegram::new(
             TelegramBase::new(
-                TelegramContent::new_value(
-                    TelegramContentType::Start,
-                    (1, 1, Some(1)),
-                    Value::String("START".to_string()),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Date,
-                    (2, 1, None),
-                    Value::Date(Date::new(2023, 7, 5, 15, 26, 41, true)),
-                    None,
-                ),
+                Marker(Some(1)),
+                Date::new(2023, 7, 5, 15, 26, 41, true).unwrap(),
                 vec![], // empty vector instead of None
-                vec![], // empty vector instead of None
-                vec![], // empty vector instead of None
-                TelegramContent::new_value(
-                    TelegramContentType::InformationType,
-                    (4, 1, None),
-                    Value::String("G".to_string()),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::End,
-                    (1, 2, Some(1)),
-                    Value::String("END".to_string()),
-                    None,
-                ),
+                InformationType::Gas,
+                Marker(Some(1)),
             ),
             TelegramData::Gas {
-                total_gas_delivered: TelegramContent::new_value(
-                    TelegramContentType::GasTotalDelivered,
-                    (5, 2, None),
-                    Value::Float(12345.123),
-                    Some(TelegramContentUnit::M3),
-                ),
+                total_gas_delivered: Volume(Decimal::new(12345123, 3)),
             },
         ),
     ];
@@ -362,18 +122,14 @@ fn test_parse_two_packets() {
         TelegramData::Electricity { voltages: v2, .. },
     ) = (&parsed[0].data, &parsed[1].data)
     {
-        if let (Some(Value::Float(first_voltage)), Some(Value::Float(second_voltage))) =
-            (&v1[0].value, &v2[0].value)
-        {
-            assert!(
-                (first_voltage - 242.7).abs() < 0.001,
-                "First telegram voltage should be 242.7"
-            );
-            assert!(
-                (second_voltage - 241.7).abs() < 0.001,
-                "Second telegram voltage should be 241.7"
-            );
-        }
+        assert!(
+            (v1[0].0 - 242.7).abs() < 0.001,
+            "First telegram voltage should be 242.7"
+        );
+        assert!(
+            (v2[0].0 - 241.7).abs() < 0.001,
+            "Second telegram voltage should be 241.7"
+        );
     }
 }
 
@@ -395,7 +151,8 @@ fn test_parse_good_sequences() {
         let filename = path.file_name().unwrap().to_str().unwrap();
         // println!("Testing file: {}", filename);
 
-        let input = fs::read_to_string(&path).expect(&format!("Failed to read file: {}", filename));
+        let input = fs::read_to_string(&path)
+            .unwrap_or_else(|_| panic!("Failed to read file: {}", filename));
 
         let result = parse(&input);
         // println!("{:?}", &result);
@@ -433,3 +190,363 @@ fn test_wrong_values() {
 
     assert!(parse_line("3.1.1#(X)").is_err());
 }
+
+#[test]
+fn test_parse_lenient_skips_bad_telegrams() {
+    let input = fs::read_to_string("examples/bad/corrupt_line.dsmr")
+        .expect("Failed to read corrupt_line.dsmr file");
+
+    assert!(parse(&input).is_err());
+
+    let (telegrams, diagnostics) = parse_lenient(&input).expect("Header should parse");
+    assert_eq!(telegrams.len(), 2);
+    assert!(matches!(telegrams[0].data, TelegramData::Gas { .. }));
+    assert!(matches!(
+        telegrams[1].data,
+        TelegramData::Electricity { .. }
+    ));
+
+    // One corrupt voltage line, one gas telegram without a gas reading
+    assert_eq!(diagnostics.len(), 2);
+    assert_eq!(diagnostics[0].line, 21);
+    assert_eq!(diagnostics[1].line, 29);
+}
+
+#[test]
+fn test_parse_lenient_good_input_has_no_diagnostics() {
+    let input = fs::read_to_string("examples/good/two_packets.dsmr")
+        .expect("Failed to read two_packets.dsmr file");
+
+    let (telegrams, diagnostics) = parse_lenient(&input).unwrap();
+    assert_eq!(telegrams.len(), 2);
+    assert!(diagnostics.is_empty());
+}
+
+#[test]
+fn test_parse_lenient_reports_unterminated_telegram() {
+    let (telegrams, diagnostics) =
+        parse_lenient("/v10\\\n1.1.0#(START)\n2.1#(23-Jul-05 15:26:41 (S))\n").unwrap();
+    assert!(telegrams.is_empty());
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, 2);
+    assert_eq!(diagnostics[0].source, "1.1.0#(START)");
+
+    // A complete telegram before it is kept
+    let input = fs::read_to_string("examples/good/two_packets.dsmr").unwrap();
+    let truncated = &input[..input.rfind("1.2.0#(END)").unwrap()];
+    let (telegrams, diagnostics) = parse_lenient(truncated).unwrap();
+    assert_eq!(telegrams.len(), 1);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].line, 24);
+}
+
+#[test]
+fn test_parse_lenient_bad_header_is_fatal() {
+    assert!(parse_lenient("/v11\\\n1.1.0#(START)\n").is_err());
+}
+
+#[test]
+fn test_parse_tree_simple_gas() {
+    let input = fs::read_to_string("examples/good/simple_gas.dsmr")
+        .expect("Failed to read simple_gas.dsmr file");
+
+    let trees = parse_tree(&input).expect("Failed to parse simple_gas example");
+    assert_eq!(trees.len(), 1);
+    assert!(matches!(trees[0].data, TelegramData::Electricity { .. }));
+    assert_eq!(trees[0].children.len(), 1);
+    assert!(matches!(
+        trees[0].children[0].data,
+        TelegramData::Gas { .. }
+    ));
+}
+
+#[test]
+fn test_parse_tree_nested_depth() {
+    let input = fs::read_to_string("examples/good_sequences/should_parse_2_recursive.dsmr")
+        .expect("Failed to read should_parse_2_recursive.dsmr file");
+
+    let trees = parse_tree(&input).expect("Failed to parse recursive example");
+    let depths: Vec<usize> = trees[0].iter().with_depth().map(|(d, _)| d).collect();
+    assert_eq!(depths, vec![0, 1, 2]);
+    assert!(matches!(
+        trees[0].iter().last().unwrap().data,
+        TelegramData::Gas { .. }
+    ));
+}
+
+#[test]
+fn test_parse_tree_flattens_to_parse() {
+    for entry in fs::read_dir("examples/good_sequences").unwrap() {
+        let input = fs::read_to_string(entry.unwrap().path()).unwrap();
+
+        let trees = parse_tree(&input).unwrap();
+        let flat = parse(&input).unwrap();
+        assert_eq!(trees.iter().map(Telegram::count).sum::<usize>(), flat.len());
+        assert!(flat.iter().all(|t| t.children.is_empty()));
+
+        let flattened: Vec<Telegram> = trees.into_iter().flat_map(Telegram::flatten).collect();
+        for (a, b) in flattened.iter().zip(flat.iter().rev()) {
+            assert_eq!(a.data, b.data);
+        }
+    }
+}
+
+#[test]
+fn test_parse_dual_tariff() {
+    let input = fs::read_to_string("examples/good/dual_tariff.dsmr")
+        .expect("Failed to read dual_tariff.dsmr file");
+
+    let telegrams = parse(&input).expect("Failed to parse dual tariff example");
+    let tariffs = match &telegrams[1].data {
+        TelegramData::Electricity {
+            tariffs: Some(tariffs),
+            ..
+        } => tariffs,
+        other => panic!("Expected tariff counters, got {other:?}"),
+    };
+    assert_eq!(
+        tariffs.consumed,
+        [Energy(Decimal::new(1000, 1)), Energy(Decimal::new(500, 1))]
+    );
+    assert_eq!(tariffs.active, Tariff::Low);
+
+    // Tariffs are all or nothing
+    for missing in ["7.5.2#(0000050.000*kWh)\n", "7.7#(0001)\n"] {
+        let incomplete = input.replacen(missing, "", 1);
+        assert!(parse(&incomplete).is_err(), "{missing}");
+    }
+    let duplicate = input.replacen("7.5.2#(", "7.5.1#(", 1);
+    assert!(parse(&duplicate).is_err());
+
+    // Without totals they are the sums of the tariffs, with totals they must match them
+    let tariffs_only = input
+        .replace("7.4.1#(0000150.000*kWh)\n", "")
+        .replace("7.4.2#(0000020.000*kWh)\n", "");
+    assert_eq!(parse(&tariffs_only).unwrap(), telegrams);
+    let mismatch = input.replacen("7.4.1#(0000150.000*kWh)", "7.4.1#(0000150.001*kWh)", 1);
+    assert!(parse(&mismatch).is_err());
+    assert!(parse_line("7.7#(0003)").is_err());
+    assert!(parse_line("7.7#(0001*kWh)").is_err());
+}
+
+#[test]
+fn test_parse_single_phase() {
+    let input = fs::read_to_string("examples/good/single_phase.dsmr")
+        .expect("Failed to read single_phase.dsmr file");
+
+    let telegrams = parse(&input).expect("Failed to parse single phase example");
+    assert_eq!(telegrams.len(), 2);
+    match &telegrams[0].data {
+        TelegramData::Electricity {
+            voltages,
+            currents,
+            powers,
+            ..
+        } => {
+            assert_eq!(voltages.len(), 1);
+            assert_eq!(currents.len(), 1);
+            assert_eq!(powers[0], Power(1.38));
+        }
+        other => panic!("Expected electricity, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_inconsistent_phases() {
+    let input = fs::read_to_string("examples/good/single_phase.dsmr").unwrap();
+
+    // A second voltage without a second current and power
+    let extra_voltage = input.replacen("7.2.1#(04.*A)", "7.1.2#(0230.0*V)\n7.2.1#(04.*A)", 1);
+    assert!(parse(&extra_voltage).is_err());
+
+    // Phases must be numbered from 1 without gaps or duplicates
+    for bad in ["7.1.2#(0231.2*V)", "7.1.0#(0231.2*V)"] {
+        let renumbered = input.replacen("7.1.1#(0231.2*V)", bad, 1);
+        assert!(parse(&renumbered).is_err(), "{bad}");
+    }
+    let missing_current = input.replacen("7.2.1#(04.*A)\n", "", 1);
+    assert!(parse(&missing_current).is_err());
+}
+
+#[test]
+fn test_parse_eventlog_entries() {
+    let input = fs::read_to_string("examples/good/reorder.dsmr").unwrap();
+
+    // The lines of an entry are joined by index, wherever they are
+    let telegrams = parse(&input).unwrap();
+    let eventlog = &telegrams[0].base.eventlog;
+    assert_eq!(eventlog.len(), 2);
+    assert_eq!(eventlog[0].index, 1);
+    assert_eq!(eventlog[0].severity, Severity::High);
+    assert_eq!(eventlog[0].message, "Power Failure");
+    assert_eq!(eventlog[1].message, "Voltage too low");
+    assert_eq!(
+        eventlog[1].date,
+        Date::new(2023, 7, 5, 13, 37, 0, true).unwrap()
+    );
+
+    let reason = |input: &str| match parse(input) {
+        Err(MainError::Diagnostic(d)) => d.reason,
+        other => panic!("Expected a diagnostic, got {other:?}"),
+    };
+    let incomplete = input.replacen("3.1.2#(L)\n", "", 1);
+    assert!(matches!(
+        reason(&incomplete),
+        MainError::MissingEventlogEntry {
+            id: 2,
+            field: "severity"
+        }
+    ));
+    let duplicate = input.replacen("3.1.2#(L)", "3.1.2#(L)\n3.1.2#(H)", 1);
+    assert!(matches!(
+        reason(&duplicate),
+        MainError::DuplicateEventlogEntry {
+            id: 2,
+            field: "severity"
+        }
+    ));
+    let bad_message = input.replacen("3.2.1#(506f", "3.2.1#(zz6f", 1);
+    assert!(matches!(reason(&bad_message), MainError::BadHexMessage(_)));
+}
+
+#[test]
+fn test_parse_water_and_heat() {
+    let input = fs::read_to_string("examples/good/water_heat.dsmr")
+        .expect("Failed to read water_heat.dsmr file");
+    assert!(parse_header("/v12\\+u").unwrap().is_utility);
+    assert!(!parse_header("/v12\\+g").unwrap().is_utility);
+
+    let telegrams = parse(&input).expect("Failed to parse water and heat example");
+    assert_eq!(telegrams.len(), 4);
+    match &telegrams[0].data {
+        TelegramData::Heat {
+            total_heat_delivered,
+        } => {
+            assert_eq!(*total_heat_delivered, HeatEnergy(Decimal::new(4537, 2)));
+        }
+        other => panic!("Expected heat, got {other:?}"),
+    }
+    assert!(matches!(telegrams[1].data, TelegramData::Water { .. }));
+
+    // Without the extension, water and heat are rejected
+    let without_extension = input.replacen("+u", "+g", 1);
+    assert!(parse(&without_extension).is_err());
+    assert!(parse_line("5.4#(00045.370*m3)").is_err());
+}
+
+#[test]
+fn test_parse_information_type_must_match_data() {
+    let reason = |input: &str| match parse(input) {
+        Err(MainError::Diagnostic(d)) => d.reason.to_string(),
+        other => panic!("Expected a diagnostic, got {other:?}"),
+    };
+    let electricity = fs::read_to_string("examples/good/two_packets.dsmr")
+        .unwrap()
+        .replacen("/v10\\", "/v12\\+g", 1);
+    assert!(parse(&electricity).is_ok());
+
+    let as_gas = electricity.replacen("4.1#(E)", "4.1#(G)", 1);
+    assert!(reason(&as_gas).contains("Information type G does not match"));
+
+    let gas = "/v12\\+g\n1.1.0#(START)\n2.1#(23-Jul-05 15:26:41 (S))\n4.1#(E)\n\
+               5.2#(12345.678*m3)\n1.2.0#(END)\n";
+    assert!(reason(gas).contains("Information type E does not match"));
+    assert!(parse(&gas.replace("4.1#(E)", "4.1#(G)")).is_ok());
+
+    // Gas and electricity readings do not belong in one telegram
+    let mixed = electricity.replacen("7.4.1#", "5.2#(12345.678*m3)\n7.4.1#", 1);
+    assert!(reason(&mixed).contains("more than one kind of meter"));
+    let water = fs::read_to_string("examples/good/water_heat.dsmr")
+        .unwrap()
+        .replacen(
+            "5.3#(00123.456*m3)",
+            "5.3#(00123.456*m3)\n5.4#(00045.120*GJ)",
+            1,
+        );
+    assert!(reason(&water).contains("more than one kind of meter"));
+}
+
+#[test]
+fn test_parse_power_quality() {
+    let input = fs::read_to_string("examples/good/power_quality.dsmr")
+        .expect("Failed to read power_quality.dsmr file");
+
+    let telegrams = parse(&input).expect("Failed to parse power quality example");
+    let quality = match &telegrams[1].data {
+        TelegramData::Electricity {
+            quality: Some(quality),
+            ..
+        } => quality,
+        other => panic!("Expected power quality, got {other:?}"),
+    };
+    assert_eq!(quality.power_failures, 4);
+    assert_eq!(quality.long_power_failures, 2);
+    assert_eq!(quality.failure_log.len(), 2);
+    assert_eq!(quality.failure_log[1].index, 2);
+    assert_eq!(quality.failure_log[1].duration, 301);
+    assert_eq!(quality.voltage_sags[0], 2);
+    assert_eq!(quality.voltage_swells.len(), 3);
+
+    // The most recent telegram does not report sags and swells
+    match &telegrams[0].data {
+        TelegramData::Electricity {
+            quality: Some(quality),
+            ..
+        } => assert!(quality.voltage_sags.is_empty()),
+        other => panic!("Expected power quality, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_parse_power_quality_errors() {
+    let input = fs::read_to_string("examples/good/power_quality.dsmr").unwrap();
+
+    for (from, to) in [
+        // Both counts are required
+        ("8.1#(00004)\n", ""),
+        // Every logged failure has a date and a duration
+        ("8.4.2#(0000000301*s)\n", ""),
+        (
+            "8.3.2#(23-Jul-04 21:30:00 (S))",
+            "8.3.1#(23-Jul-04 21:30:00 (S))",
+        ),
+        // Sags are reported for every phase
+        ("8.5.3#(00000)\n", ""),
+    ] {
+        let bad = input.replacen(from, to, 1);
+        assert!(parse(&bad).is_err(), "{from}");
+    }
+    assert!(parse_line("8.1#(4)").is_err());
+    assert!(parse_line("8.4.1#(0000000240*V)").is_err());
+}
+
+#[test]
+fn test_parse_equipment_id() {
+    let input = fs::read_to_string("examples/good/multi_meter.dsmr")
+        .expect("Failed to read multi_meter.dsmr file");
+
+    let mut telegrams = parse(&input).expect("Failed to parse multi meter example");
+    telegrams.reverse();
+    let ids: Vec<Option<String>> = telegrams
+        .iter()
+        .map(|t| t.base.equipment_id.clone())
+        .collect();
+    assert_eq!(
+        ids,
+        ["E0026000000001", "E0026000000002"]
+            .repeat(2)
+            .into_iter()
+            .map(|id| Some(id.to_string()))
+            .collect::<Vec<_>>()
+    );
+
+    // The identifier is optional
+    let single = fs::read_to_string("examples/good/single_phase.dsmr").unwrap();
+    assert_eq!(parse(&single).unwrap()[0].base.equipment_id, None);
+
+    // Like eventlog messages, it is hex encoded
+    assert!(parse_line("2.2#(45300)").is_err());
+    assert!(parse_line("2.2#(45zz)").is_err());
+    assert!(parse_line(&format!("2.2#({})", "45".repeat(49))).is_err());
+    assert!(parse_line("2.2#(4530)").is_ok());
+}
@@ -0,0 +1,149 @@
+use dsmr_assignment::decimal::Decimal;
+use dsmr_assignment::export::json_value;
+use dsmr_assignment::parser::{parse, parse_header, parse_line};
+use dsmr_assignment::registry::*;
+use dsmr_assignment::serializer::serialize;
+use dsmr_assignment::telegram::*;
+
+const TELEGRAM: &str = "/v10\\
+1.1.0#(START)
+2.1#(23-Jul-05 15:26:41 (S))
+4.1#(E)
+7.1.1#(0241.7*V)
+7.1.2#(0240.6*V)
+7.1.3#(241.92*V)
+7.2.1#(01.*A)
+7.2.2#(10.*A)
+7.2.3#(0.5*A)
+7.3.1#(+001.00*kW)
+7.3.2#(-05.010*kW)
+7.3.3#(+02.500*kW)
+7.4.1#(0011454892.*kWh)
+7.4.2#(0000001245.*kWh)
+9.1#(021.5*C)
+1.2.0#(END)
+";
+
+fn temperature() -> ContentSpec {
+    ContentSpec {
+        content_type: TelegramContentType::Custom("temperature".to_string()),
+        name: "temperature value",
+        id: IdPattern::new(9, 1, SubId::Absent),
+        unit: Some(TelegramContentUnit::Custom("C".to_string())),
+        kind: ValueKind::Float,
+        format: ValueFormat::Number {
+            width: 5,
+            decimals: 1..=1,
+            point_optional: false,
+            signed: true,
+        },
+    }
+}
+
+#[test]
+fn test_builtin_lookup() {
+    let voltage = spec_for_id(&(7, 1, Some(3))).unwrap();
+    assert_eq!(voltage.content_type, TelegramContentType::Voltage);
+    assert_eq!(voltage.unit, Some(TelegramContentUnit::V));
+    assert_eq!(voltage.kind, ValueKind::Float);
+
+    assert_eq!(
+        spec_for_id(&(7, 4, Some(2))).unwrap().content_type,
+        TelegramContentType::TotalProduced
+    );
+    assert!(spec_for_id(&(7, 4, Some(3))).is_none());
+    assert!(spec_for_id(&(2, 1, Some(1))).is_none());
+
+    // Every builtin spec can be found back by its type
+    for spec in &BUILTIN {
+        assert_eq!(spec_for_type(&spec.content_type), Some(spec));
+    }
+}
+
+#[test]
+fn test_id_patterns() {
+    let any = IdPattern::new(1, 1, SubId::Any);
+    assert!(any.matches(&(1, 1, None)));
+    assert!(any.matches(&(1, 1, Some(0))));
+    assert!(!any.matches(&(1, 2, None)));
+
+    let exactly = IdPattern::new(7, 4, SubId::Exactly(1));
+    assert!(exactly.matches(&(7, 4, Some(1))));
+    assert!(!exactly.matches(&(7, 4, None)));
+
+    assert!(any.overlaps(&IdPattern::new(1, 1, SubId::Absent)));
+    assert!(exactly.overlaps(&IdPattern::new(7, 4, SubId::Present)));
+    assert!(!exactly.overlaps(&IdPattern::new(7, 4, SubId::Exactly(2))));
+    assert!(!exactly.overlaps(&IdPattern::new(7, 4, SubId::Absent)));
+}
+
+#[test]
+fn test_value_formats() {
+    assert!(ValueFormat::OneOf(&["H", "L"]).matches("L"));
+    assert!(!ValueFormat::OneOf(&["H", "L"]).matches("M"));
+    assert!(ValueFormat::MaxLength(2).matches("ab"));
+    assert!(!ValueFormat::MaxLength(2).matches("abc"));
+
+    let current = ValueFormat::Number {
+        width: 3,
+        decimals: 0..=1,
+        point_optional: true,
+        signed: false,
+    };
+    assert!(current.matches("0.5"));
+    assert!(current.matches("01."));
+    assert!(current.matches("10"));
+    assert!(!current.matches("1"));
+    assert!(!current.matches("0.55"));
+    assert!(!current.matches("-0.5"));
+}
+
+#[test]
+fn test_register_rejects_overlaps() {
+    let mut builtin_id = temperature();
+    builtin_id.id = IdPattern::new(7, 1, SubId::Exactly(4));
+    assert!(register(builtin_id).is_err());
+
+    let mut not_custom = temperature();
+    not_custom.content_type = TelegramContentType::Voltage;
+    not_custom.id = IdPattern::new(9, 9, SubId::Absent);
+    assert!(register(not_custom).is_err());
+
+    let mut first = temperature();
+    first.content_type = TelegramContentType::Custom("humidity".to_string());
+    first.id = IdPattern::new(9, 2, SubId::Present);
+    register(first.clone()).unwrap();
+    first.content_type = TelegramContentType::Custom("other humidity".to_string());
+    first.id = IdPattern::new(9, 2, SubId::Exactly(1));
+    assert!(register(first).is_err());
+}
+
+#[test]
+fn test_custom_content_is_preserved() {
+    register(temperature()).unwrap();
+
+    let content = parse_line("9.1#(-003.5*C)").unwrap();
+    assert_eq!(content.value, Some(Value::Number(Decimal::new(-35, 1))));
+    assert!(parse_line("9.1#(3.5*C)").is_err());
+    assert!(parse_line("9.1#(003.5*V)").is_err());
+
+    let telegrams = parse(TELEGRAM).unwrap();
+    let extra = &telegrams[0].extra;
+    assert_eq!(extra.len(), 1);
+    assert_eq!(
+        extra[0].telegram_content_type,
+        TelegramContentType::Custom("temperature".to_string())
+    );
+    assert_eq!(extra[0].value, Some(Value::Number(Decimal::new(215, 1))));
+
+    let config = parse_header("/v10\\").unwrap();
+    let serialized = serialize(&config, &telegrams).unwrap();
+    assert!(serialized.contains("9.1#(021.5*C)\n1.2.0#(END)"));
+    assert_eq!(parse(&serialized).unwrap(), telegrams);
+
+    let json = json_value(&config, &telegrams).unwrap();
+    let extra = &json["telegrams"][0]["extra"][0];
+    assert_eq!(extra["type"], "temperature");
+    assert_eq!(extra["value"], "21.5");
+    assert_eq!(extra["unit"], "C");
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::assertions_on_constants)]
+
 use dsmr_assignment::runner::run;
 
 #[test]
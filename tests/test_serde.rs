@@ -0,0 +1,49 @@
+#![cfg(feature = "serde")]
+
+use std::fs;
+
+use dsmr_assignment::decimal::Decimal;
+use dsmr_assignment::parser::parse_tree;
+use dsmr_assignment::telegram::*;
+
+#[test]
+fn test_serde_round_trip() {
+    for dir in ["examples/good", "examples/good_sequences"] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let path = entry.unwrap().path();
+            let input = fs::read_to_string(&path).unwrap();
+            let telegrams = parse_tree(&input).unwrap();
+
+            let json = serde_json::to_string(&telegrams).unwrap();
+            let loaded: Vec<Telegram> = serde_json::from_str(&json).unwrap();
+            assert_eq!(loaded, telegrams, "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn test_serde_decimal_keeps_precision() {
+    let volume = Volume(Decimal::new(12345120, 3));
+    let json = serde_json::to_string(&volume).unwrap();
+    assert_eq!(json, "\"12345.120\"");
+
+    let loaded: Volume = serde_json::from_str(&json).unwrap();
+    assert_eq!(loaded.0.scale(), 3);
+    assert!(serde_json::from_str::<Volume>("\"12x\"").is_err());
+}
+
+#[test]
+fn test_serde_date_is_validated() {
+    let date = Date::new(2023, 7, 5, 15, 26, 41, true).unwrap();
+    let json = serde_json::to_string(&date).unwrap();
+    assert!(!json.contains("timestamp"));
+    assert_eq!(serde_json::from_str::<Date>(&json).unwrap(), date);
+
+    // Summer time in January, and a day that does not exist
+    let winter = json.replace("\"month\":7", "\"month\":1");
+    assert!(serde_json::from_str::<Date>(&winter).is_err());
+    let invalid = json
+        .replace("\"day\":5", "\"day\":31")
+        .replace("\"month\":7", "\"month\":6");
+    assert!(serde_json::from_str::<Date>(&invalid).is_err());
+}
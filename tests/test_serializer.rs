@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::Path;
+
+use dsmr_assignment::decimal::Decimal;
+use dsmr_assignment::parser::*;
+use dsmr_assignment::serializer::*;
+use dsmr_assignment::telegram::*;
+
+fn assert_round_trip(path: &Path) {
+    let input = fs::read_to_string(path).expect("Failed to read example");
+    let config = parse_header(input.lines().next().unwrap()).unwrap();
+    let telegrams = parse_tree(&input).unwrap();
+
+    let serialized = serialize(&config, &telegrams).unwrap();
+    let reparsed = parse_tree(&serialized)
+        .unwrap_or_else(|e| panic!("Failed to reparse {}: {e}\n{serialized}", path.display()));
+    assert_eq!(reparsed, telegrams, "Round trip of {}", path.display());
+
+    // Serializing is deterministic, so a second round trip is a fixpoint
+    assert_eq!(serialize(&config, &reparsed).unwrap(), serialized);
+}
+
+#[test]
+fn test_round_trip_examples() {
+    for dir in ["examples/good", "examples/good_sequences"] {
+        for entry in fs::read_dir(dir).unwrap() {
+            assert_round_trip(&entry.unwrap().path());
+        }
+    }
+}
+
+#[test]
+fn test_round_trip_flat_parse() {
+    for path in [
+        "examples/good/two_packets.dsmr",
+        "examples/good_sequences/should_parse_0_recursive.dsmr",
+    ] {
+        // Telegrams that differ, so the order matters
+        let input =
+            fs::read_to_string(path)
+                .unwrap()
+                .replacen("7.1.1#(0241.7*V)", "7.1.1#(0241.8*V)", 1);
+        let config = parse_header(input.lines().next().unwrap()).unwrap();
+        let telegrams = parse(&input).unwrap();
+
+        let serialized = serialize_flat(&config, &telegrams).unwrap();
+        assert_eq!(
+            parse(&serialized).unwrap(),
+            telegrams,
+            "Round trip of {path}"
+        );
+    }
+}
+
+#[test]
+fn test_serialize_header() {
+    for header in ["/v10\\", "/v12\\", "/v12\\+g", "/v12\\+r", "/v12\\+gr"] {
+        assert_eq!(serialize_header(&parse_header(header).unwrap()), header);
+    }
+}
+
+#[test]
+fn test_serialize_padded_values() {
+    for line in [
+        "7.1.1#(0241.7*V)",
+        "7.1.3#(241.92*V)",
+        "7.2.1#(01.*A)",
+        "7.2.3#(0.5*A)",
+        "7.3.1#(00001.*kW)",
+        "7.3.2#(-005.01*kW)",
+        "7.4.1#(0011454892.*kWh)",
+        "5.2#(12345.123*m3)",
+        // A line keeps the decimals its value was written with
+        "5.2#(12345.120*m3)",
+        "7.1.1#(230.10*V)",
+        "2.1#(23-Jul-05 15:26:41 (S))",
+        "3.2.1#(506f776572204661696c757265)",
+    ] {
+        let content = parse_line(line).unwrap();
+        assert_eq!(serialize_content(&content).unwrap(), line);
+    }
+}
+
+#[test]
+fn test_serialize_telegram_keeps_counter_decimals() {
+    let input = "/v12\\+g\n1.1.0#(START)\n2.1#(23-Jul-05 15:26:41 (S))\n4.1#(G)\n\
+                 5.2#(12345.120*m3)\n1.2.0#(END)\n";
+    let config = parse_header("/v12\\+g").unwrap();
+    let serialized = serialize(&config, &parse_tree(input).unwrap()).unwrap();
+    assert!(serialized.contains("5.2#(12345.120*m3)"), "{serialized}");
+
+    // Instantaneous readings are plain numbers in a telegram, so only their value is kept
+    let input = fs::read_to_string("examples/good/two_packets.dsmr")
+        .unwrap()
+        .replacen("7.1.1#(0241.7*V)", "7.1.1#(241.70*V)", 1);
+    let config = parse_header(input.lines().next().unwrap()).unwrap();
+    let serialized = serialize(&config, &parse_tree(&input).unwrap()).unwrap();
+    assert!(serialized.contains("7.1.1#(0241.7*V)"), "{serialized}");
+}
+
+#[test]
+fn test_serialize_unrepresentable_value() {
+    let content = TelegramContent::new_value(
+        TelegramContentType::Voltage,
+        (7, 1, Some(1)),
+        Value::Number(Decimal::new(123456, 1)),
+        Some(TelegramContentUnit::V),
+    );
+    assert!(serialize_content(&content).is_err());
+}
+
+#[test]
+fn test_serialize_date_four_digit_year() {
+    let date = Date::new(1999, 12, 31, 23, 59, 59, false).unwrap();
+    let serialized = serialize_date(&date).unwrap();
+    assert_eq!(serialized, "1999-Dec-31 23:59:59 (W)");
+    assert_eq!(parse_date(&serialized).unwrap(), date);
+
+    let date = Date::new(999, 12, 31, 23, 59, 59, false).unwrap();
+    assert!(serialize_date(&date).is_err());
+}
@@ -0,0 +1,103 @@
+use std::fs;
+use std::io::BufReader;
+
+use dsmr_assignment::parser::*;
+use dsmr_assignment::stream::*;
+use dsmr_assignment::telegram::*;
+
+#[test]
+fn test_stream_byte_by_byte() {
+    let input = fs::read_to_string("examples/good/two_packets.dsmr")
+        .expect("Failed to read two_packets.dsmr file");
+
+    let mut stream = TelegramStream::new();
+    let mut telegrams = Vec::new();
+    for byte in input.as_bytes() {
+        stream.push(&[*byte]).unwrap();
+        while let Some(telegram) = stream.next_telegram() {
+            telegrams.push(telegram);
+        }
+    }
+    stream.finish().unwrap();
+    while let Some(telegram) = stream.next_telegram() {
+        telegrams.push(telegram);
+    }
+
+    // parse() returns the telegrams in reverse order of completion
+    let mut expected = parse(&input).unwrap();
+    expected.reverse();
+    assert_eq!(telegrams.len(), expected.len());
+    for (streamed, parsed) in telegrams.iter().zip(expected.iter()) {
+        assert_eq!(streamed.data, parsed.data);
+    }
+}
+
+#[test]
+fn test_stream_yields_on_end() {
+    let input = fs::read_to_string("examples/good/two_packets.dsmr")
+        .expect("Failed to read two_packets.dsmr file");
+    let (first, second) = input.split_at(input.find("\n\n").unwrap() + 1);
+
+    let mut stream = TelegramStream::new();
+    stream.push(first.as_bytes()).unwrap();
+    assert!(stream.next_telegram().is_some());
+    assert!(stream.next_telegram().is_none());
+
+    stream.push(second.as_bytes()).unwrap();
+    assert!(stream.next_telegram().is_some());
+}
+
+#[test]
+fn test_stream_recursive_keeps_state_between_chunks() {
+    let input = fs::read_to_string("examples/good/simple_gas.dsmr")
+        .expect("Failed to read simple_gas.dsmr file");
+
+    let mut stream = TelegramStream::new();
+    for chunk in input.as_bytes().chunks(7) {
+        stream.push(chunk).unwrap();
+    }
+    stream.finish().unwrap();
+
+    let child = stream.next_telegram().expect("Missing child telegram");
+    let parent = stream.next_telegram().expect("Missing parent telegram");
+    assert!(matches!(child.data, TelegramData::Gas { .. }));
+    assert!(matches!(parent.data, TelegramData::Electricity { .. }));
+    assert!(stream.next_telegram().is_none());
+}
+
+#[test]
+fn test_reader_good_sequences() {
+    for entry in fs::read_dir("examples/good_sequences").unwrap() {
+        let path = entry.unwrap().path();
+        let input = fs::read_to_string(&path).unwrap();
+
+        // A tiny buffer forces the reader to split lines over many chunks
+        let reader = BufReader::with_capacity(16, input.as_bytes());
+        let telegrams = TelegramReader::new(reader)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap_or_else(|e| panic!("Failed to stream {}: {e}", path.display()));
+
+        assert_eq!(telegrams.len(), parse(&input).unwrap().len());
+    }
+}
+
+#[test]
+fn test_stream_rejects_long_lines() {
+    let mut stream = TelegramStream::new();
+    stream.push(b"/v10\\\n1.1.0#(START)\n3.2.1#(").unwrap();
+    assert!(stream.push(&[b'4'; MAX_LINE_LENGTH]).is_err());
+}
+
+#[test]
+fn test_reader_reports_errors() {
+    let input = "/v10\\\n1.1.0#(START)\n7.1.1#(02x1.7*V)\n";
+    let mut reader = TelegramReader::new(input.as_bytes());
+    assert!(matches!(reader.next(), Some(Err(_))));
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn test_reader_empty_input() {
+    let mut reader = TelegramReader::new("".as_bytes());
+    assert!(matches!(reader.next(), Some(Err(_))));
+}
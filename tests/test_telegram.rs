@@ -1,3 +1,6 @@
+#![allow(clippy::bool_assert_comparison)]
+
+use dsmr_assignment::decimal::Decimal;
 use dsmr_assignment::traits::Validatable;
 
 use dsmr_assignment::parser::*;
@@ -20,7 +23,7 @@ fn test_telegram_content_tdate() {
     let t = TelegramContent::new_value(
         TelegramContentType::EventlogDate,
         (3, 3, Some(1)),
-        Value::Date(Date::new(1, 1, 1, 1, 1, 1, false)),
+        Value::Date(Date::new(1, 1, 1, 1, 1, 1, false).unwrap()),
         None,
     );
     assert_eq!(true, t.validate());
@@ -31,7 +34,7 @@ fn test_telegram_content_f32() {
     let t = TelegramContent::new_value(
         TelegramContentType::Power,
         (7, 3, Some(1)),
-        Value::Float(1.0),
+        Value::Number(Decimal::new(10, 1)),
         Some(TelegramContentUnit::KW),
     );
     assert_eq!(true, t.validate());
@@ -43,134 +46,64 @@ fn test_telegram_content_f32() {
 fn test_telegram_constructor() {
     let _t = Telegram::new(
         TelegramBase::new(
-            TelegramContent::new_value(
-                TelegramContentType::Start,
-                (1, 1, Some(0)),
-                Value::String("START".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::Date,
-                (2, 1, None),
-                Value::Date(Date::new(2002, 2, 14, 0, 0, 0, true)),
-                None,
-            ),
-            vec![(
-                1,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogSeverity,
-                    (3, 1, Some(1)),
-                    Value::String("H".to_string()),
-                    None,
-                ),
-            )],
-            vec![(
-                1,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogMessage,
-                    (3, 2, Some(1)),
-                    Value::String("Power outage detected".to_string()),
-                    None,
-                ),
-            )],
-            vec![(
-                1,
-                TelegramContent::new_value(
-                    TelegramContentType::EventlogDate,
-                    (3, 3, Some(1)),
-                    Value::Date(Date::new(2002, 2, 14, 14, 30, 0, true)),
-                    None,
-                ),
-            )],
-            TelegramContent::new_value(
-                TelegramContentType::InformationType,
-                (4, 1, None),
-                Value::String("E".to_string()),
-                None,
-            ),
-            TelegramContent::new_value(
-                TelegramContentType::End,
-                (1, 2, Some(0)),
-                Value::String("END".to_string()),
-                None,
-            ),
+            Marker(Some(0)),
+            Date::new(2002, 2, 14, 0, 0, 0, false).unwrap(),
+            vec![EventLogEntry {
+                index: 1,
+                severity: Severity::High,
+                message: "Power outage detected".to_string(),
+                date: Date::new(2002, 2, 14, 14, 30, 0, false).unwrap(),
+            }],
+            InformationType::Electricity,
+            Marker(Some(0)),
         ),
         TelegramData::Electricity {
-            voltages: [
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(1)),
-                    Value::Float(230.5),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(2)),
-                    Value::Float(231.2),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Voltage,
-                    (7, 1, Some(3)),
-                    Value::Float(229.8),
-                    None,
-                ),
-            ],
-            currents: [
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(1)),
-                    Value::Float(15.3),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(2)),
-                    Value::Float(12.7),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Current,
-                    (7, 2, Some(3)),
-                    Value::Float(14.1),
-                    None,
-                ),
-            ],
-            powers: [
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(1)),
-                    Value::Float(3.524),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(2)),
-                    Value::Float(2.937),
-                    None,
-                ),
-                TelegramContent::new_value(
-                    TelegramContentType::Power,
-                    (7, 3, Some(3)),
-                    Value::Float(3.240),
-                    None,
-                ),
-            ],
-            total_consumed: TelegramContent::new_value(
-                TelegramContentType::TotalConsumed,
-                (7, 4, Some(1)),
-                Value::Float(12345.67),
-                None,
-            ),
-            total_produced: TelegramContent::new_value(
-                TelegramContentType::TotalProduced,
-                (7, 4, Some(2)),
-                Value::Float(0.0),
-                None,
-            ),
+            voltages: vec![Voltage(230.5), Voltage(231.2), Voltage(229.8)],
+            currents: vec![Current(15.3), Current(12.7), Current(14.1)],
+            powers: vec![Power(3.524), Power(2.937), Power(3.240)],
+            total_consumed: Energy(Decimal::new(1234567, 2)),
+            total_produced: Energy(Decimal::new(0, 1)),
+            tariffs: None,
+            quality: None,
         },
     );
 }
+
+#[test]
+fn test_typed_codes_round_trip() {
+    for severity in [Severity::High, Severity::Low] {
+        assert_eq!(Severity::try_from(severity.code()).unwrap(), severity);
+    }
+    for information_type in [
+        InformationType::Electricity,
+        InformationType::Gas,
+        InformationType::Water,
+        InformationType::Heat,
+    ] {
+        assert_eq!(
+            InformationType::try_from(information_type.code()).unwrap(),
+            information_type
+        );
+    }
+    for tariff in [Tariff::Low, Tariff::Normal] {
+        assert_eq!(Tariff::try_from(tariff.code()).unwrap(), tariff);
+    }
+
+    assert!(matches!(
+        Severity::try_from("X"),
+        Err(dsmr_assignment::error::MainError::UnknownSeverity(_))
+    ));
+    assert!(InformationType::try_from("X").is_err());
+    assert!(Tariff::try_from("0003").is_err());
+}
+
+#[test]
+fn test_quantity_units() {
+    assert_eq!(Voltage::UNIT, TelegramContentUnit::V);
+    assert_eq!(Energy::UNIT, TelegramContentUnit::KWH);
+    assert_eq!(Volume::new(Decimal::new(15, 1)).value(), 1.5);
+    assert_eq!(Voltage(230.1).decimal().unwrap(), Decimal::new(2301, 1));
+}
 // 2. End
 
 // 3. Additional tests
@@ -296,6 +229,109 @@ fn test_parse_date_winter_time() {
     assert_eq!(result.dst, false);
 }
 
+#[test]
+fn test_date_calendar_validation() {
+    assert!(Date::new(2024, 2, 29, 12, 0, 0, false).is_ok());
+    assert!(Date::new(2023, 2, 29, 12, 0, 0, false).is_err());
+    assert!(Date::new(2000, 2, 29, 12, 0, 0, false).is_ok());
+    assert!(Date::new(2100, 2, 29, 12, 0, 0, false).is_err());
+    assert!(Date::new(2023, 4, 31, 12, 0, 0, true).is_err());
+    assert!(Date::new(2023, 13, 1, 12, 0, 0, false).is_err());
+    assert!(parse_date("23-Feb-29 12:00:00 (W)").is_err());
+    assert!(parse_date("23-Sep-31 12:00:00 (S)").is_err());
+
+    // Also for dates that did not go through Date::new
+    let mut date = Date::new(2023, 1, 31, 12, 0, 0, false).unwrap();
+    assert!(date.validate());
+    date.month = 2;
+    assert!(!date.validate());
+
+    assert_eq!(days_in_month(2024, 2), Some(29));
+    assert_eq!(days_in_month(2023, 11), Some(30));
+    assert_eq!(days_in_month(2023, 0), None);
+    assert!(is_leap_year(2000) && !is_leap_year(1900));
+}
+
+#[test]
+fn test_parse_date_years() {
+    assert_eq!(parse_date("2023-Jul-05 15:26:41 (S)").unwrap().year, 2023);
+    assert_eq!(parse_date("1998-Jul-05 15:26:41 (S)").unwrap().year, 1998);
+    assert_eq!(parse_date("99-Jul-05 15:26:41 (S)").unwrap().year, 2099);
+    assert!(parse_date("023-Jul-05 15:26:41 (S)").is_err());
+    assert!(parse_date("+3-Jul-05 15:26:41 (S)").is_err());
+
+    let pivot = YearPivot(70);
+    assert_eq!(
+        parse_date_with("69-Jul-05 15:26:41 (S)", pivot)
+            .unwrap()
+            .year,
+        2069
+    );
+    assert_eq!(
+        parse_date_with("70-Jul-05 15:26:41 (S)", pivot)
+            .unwrap()
+            .year,
+        1970
+    );
+    assert_eq!(
+        parse_date_with("2070-Jul-05 15:26:41 (S)", pivot)
+            .unwrap()
+            .year,
+        2070
+    );
+}
+
+#[test]
+fn test_date_ordering_and_arithmetic() {
+    let earlier = parse_date("23-Jul-05 15:26:41 (S)").unwrap();
+    let later = parse_date("23-Jul-05 16:00:00 (S)").unwrap();
+    assert!(earlier < later);
+    assert_eq!((&later - &earlier).num_seconds(), 33 * 60 + 19);
+    assert_eq!((&earlier - &later).num_seconds(), -(33 * 60 + 19));
+
+    // 02:30 summer time comes before 02:30 winter time on the night of the switch
+    let summer = parse_date("23-Oct-29 02:30:00 (S)").unwrap();
+    let winter = parse_date("23-Oct-29 02:30:00 (W)").unwrap();
+    assert!(summer < winter);
+    assert_eq!((&winter - &summer).num_hours(), 1);
+
+    // Adding time gives the local date, switching the daylight saving time flag as needed
+    let next = summer.checked_add(chrono::TimeDelta::hours(1)).unwrap();
+    assert_eq!(next, winter);
+    let back = next.checked_add(chrono::TimeDelta::hours(-1)).unwrap();
+    assert_eq!(back, summer);
+    let mut dates = vec![winter.clone(), later.clone(), earlier.clone()];
+    dates.sort();
+    assert_eq!(dates, [earlier, later, winter]);
+}
+
+#[test]
+fn test_date_dst_flag_matches_calendar() {
+    // 2023-07-05 15:26:41 CEST is 13:26:41 UTC
+    let summer = parse_date("23-Jul-05 15:26:41 (S)").unwrap();
+    assert_eq!(summer.timestamp, 1688563601);
+    let winter = parse_date("23-Dec-15 08:30:00 (W)").unwrap();
+    assert_eq!(winter.timestamp, 1702625400);
+
+    // Markers that contradict the calendar
+    assert!(parse_date("23-Jul-05 15:26:41 (W)").is_err());
+    assert!(parse_date("23-Dec-15 08:30:00 (S)").is_err());
+    assert!(Date::new(2023, 3, 26, 1, 59, 59, false).is_ok());
+    assert!(Date::new(2023, 3, 26, 3, 0, 0, false).is_err());
+    assert!(Date::new(2023, 10, 29, 3, 0, 0, true).is_err());
+
+    // The hour that is skipped when summer time starts does not exist
+    assert!(parse_date("23-Mar-26 02:30:00 (S)").is_err());
+    assert!(parse_date("23-Mar-26 02:30:00 (W)").is_err());
+
+    // The hour that repeats when summer time ends exists in both
+    let first = parse_date("23-Oct-29 02:30:00 (S)").unwrap();
+    let second = parse_date("23-Oct-29 02:30:00 (W)").unwrap();
+    assert_eq!(second.timestamp - first.timestamp, 3600);
+    assert_eq!(Date::from_timestamp(first.timestamp).unwrap(), first);
+    assert_eq!(Date::from_timestamp(second.timestamp).unwrap(), second);
+}
+
 #[test]
 fn test_parse_date_invalid_format() {
     let result = parse_date("invalid-date");